@@ -0,0 +1,94 @@
+#ifndef FCS_H
+#define FCS_H
+
+/* Generated by cbindgen. Do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * A solution is found.
+ */
+#define FCS_SOLVED 1
+
+/**
+ * No solution is found within the limits.
+ */
+#define FCS_UNSOLVED 0
+
+/**
+ * The input is invalid.
+ */
+#define FCS_INVALID -1
+
+/**
+ * The solver with the result of the last search.
+ */
+typedef struct fcs_solver fcs_solver;
+
+/**
+ * A move of the top card from one desk spot to another.
+ * Spots are numbered 0-3 for bases, 4-7 for cells and 8-15 for piles.
+ */
+typedef struct fcs_move {
+  uint8_t giver;
+  uint8_t taker;
+} fcs_move;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Creates a solver. It should be released with [`fcs_free`].
+ */
+struct fcs_solver *fcs_solver_new(void);
+
+/**
+ * Solves the deal, processing at most `done_max` positions (0 for the default limit).
+ * Returns [`FCS_SOLVED`], [`FCS_UNSOLVED`] or [`FCS_INVALID`] for a null solver.
+ *
+ * # Safety
+ * The solver should be created by [`fcs_solver_new`] and not yet freed.
+ */
+int fcs_solve_deal(struct fcs_solver *solver, uint64_t seed, size_t done_max);
+
+/**
+ * Solves the board given as a null-terminated UTF-8 text (see `parse_board` of the library),
+ * processing at most `done_max` positions (0 for the default limit).
+ * Returns [`FCS_SOLVED`], [`FCS_UNSOLVED`] or [`FCS_INVALID`] for a null solver or a malformed board.
+ *
+ * # Safety
+ * The solver should be created by [`fcs_solver_new`] and not yet freed.
+ * The text should be null or a valid null-terminated string.
+ */
+int fcs_solve_board(struct fcs_solver *solver,
+                    const char *text,
+                    size_t done_max);
+
+/**
+ * Copies up to `len` moves of the last found solution into `moves` and returns the solution length.
+ * Call it with a null buffer to get the length. Returns 0 if there is no solution.
+ *
+ * # Safety
+ * The solver should be null or created by [`fcs_solver_new`] and not yet freed.
+ * The buffer should be null or valid for writing `len` moves.
+ */
+size_t fcs_result_moves(const struct fcs_solver *solver, struct fcs_move *moves, size_t len);
+
+/**
+ * Releases the solver. Null is ignored.
+ *
+ * # Safety
+ * The solver should be null or created by [`fcs_solver_new`] and not yet freed.
+ */
+void fcs_free(struct fcs_solver *solver);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* FCS_H */
@@ -0,0 +1,163 @@
+//! # C API
+//! Embeds the solver in native game clients. The header is `include/fcs.h`.
+//!
+//! A typical session:
+//! ```c
+//! fcs_solver *solver = fcs_solver_new();
+//! if (fcs_solve_deal(solver, 173205951, 0) == FCS_SOLVED) {
+//!     size_t len = fcs_result_moves(solver, NULL, 0);
+//!     fcs_move *moves = malloc(len * sizeof(fcs_move));
+//!     fcs_result_moves(solver, moves, len);
+//!     ...
+//! }
+//! fcs_free(solver);
+//! ```
+
+use std::ffi::CStr;
+use std::os::raw::{c_char, c_int};
+use std::ptr;
+
+use freecell_solver::freecell::{parse_board, Game, Limits, Path, Solver};
+
+/// A solution is found.
+pub const FCS_SOLVED: c_int = 1;
+/// No solution is found within the limits.
+pub const FCS_UNSOLVED: c_int = 0;
+/// The input is invalid.
+pub const FCS_INVALID: c_int = -1;
+
+/// The solver with the result of the last search.
+pub struct FcsSolver {
+    solver: Solver,
+    result: Option<Path>,
+}
+
+/// A move of the top card from one desk spot to another.
+/// Spots are numbered 0-3 for bases, 4-7 for cells and 8-15 for piles.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FcsMove {
+    pub giver: u8,
+    pub taker: u8,
+}
+
+impl FcsSolver {
+    fn solve(&mut self, done_max: usize) -> c_int {
+        let limits = Limits {
+            done_max: if done_max > 0 {
+                done_max
+            } else {
+                Limits::default().done_max
+            },
+            any: true,
+            ..Limits::default()
+        };
+        self.result = self.solver.solve(&limits).cloned();
+        match self.result {
+            Some(_) => FCS_SOLVED,
+            None => FCS_UNSOLVED,
+        }
+    }
+}
+
+/// Creates a solver. It should be released with [`fcs_free`].
+#[no_mangle]
+pub extern "C" fn fcs_solver_new() -> *mut FcsSolver {
+    Box::into_raw(Box::new(FcsSolver {
+        solver: Solver::new(),
+        result: None,
+    }))
+}
+
+/// Solves the deal, processing at most `done_max` positions (0 for the default limit).
+/// Returns [`FCS_SOLVED`], [`FCS_UNSOLVED`] or [`FCS_INVALID`] for a null solver.
+///
+/// # Safety
+/// The solver should be created by [`fcs_solver_new`] and not yet freed.
+#[no_mangle]
+pub unsafe extern "C" fn fcs_solve_deal(
+    solver: *mut FcsSolver,
+    seed: u64,
+    done_max: usize,
+) -> c_int {
+    match solver.as_mut() {
+        Some(solver) => {
+            solver.solver.deal(seed);
+            solver.solve(done_max)
+        }
+        None => FCS_INVALID,
+    }
+}
+
+/// Solves the board given as a null-terminated UTF-8 text (see `parse_board` of the library),
+/// processing at most `done_max` positions (0 for the default limit).
+/// Returns [`FCS_SOLVED`], [`FCS_UNSOLVED`] or [`FCS_INVALID`] for a null solver or a malformed board.
+///
+/// # Safety
+/// The solver should be created by [`fcs_solver_new`] and not yet freed.
+/// The text should be null or a valid null-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn fcs_solve_board(
+    solver: *mut FcsSolver,
+    text: *const c_char,
+    done_max: usize,
+) -> c_int {
+    let solver = match solver.as_mut() {
+        Some(solver) => solver,
+        None => return FCS_INVALID,
+    };
+    solver.result = None;
+    if text.is_null() {
+        return FCS_INVALID;
+    }
+    let cards = match CStr::from_ptr(text).to_str().ok().and_then(parse_board) {
+        Some(cards) => cards,
+        None => return FCS_INVALID,
+    };
+
+    let mut game = Game::new();
+    game.deal(&cards);
+    solver.solver.start_from(&game);
+    solver.solve(done_max)
+}
+
+/// Copies up to `len` moves of the last found solution into `moves` and returns the solution length.
+/// Call it with a null buffer to get the length. Returns 0 if there is no solution.
+///
+/// # Safety
+/// The solver should be null or created by [`fcs_solver_new`] and not yet freed.
+/// The buffer should be null or valid for writing `len` moves.
+#[no_mangle]
+pub unsafe extern "C" fn fcs_result_moves(
+    solver: *const FcsSolver,
+    moves: *mut FcsMove,
+    len: usize,
+) -> usize {
+    let path = match solver.as_ref().and_then(|solver| solver.result.as_ref()) {
+        Some(path) => path,
+        None => return 0,
+    };
+    if !moves.is_null() {
+        for (i, mv) in path.iter().take(len).enumerate() {
+            ptr::write(
+                moves.add(i),
+                FcsMove {
+                    giver: mv.giver().index() as u8,
+                    taker: mv.taker().index() as u8,
+                },
+            );
+        }
+    }
+    path.len()
+}
+
+/// Releases the solver. Null is ignored.
+///
+/// # Safety
+/// The solver should be null or created by [`fcs_solver_new`] and not yet freed.
+#[no_mangle]
+pub unsafe extern "C" fn fcs_free(solver: *mut FcsSolver) {
+    if !solver.is_null() {
+        drop(Box::from_raw(solver));
+    }
+}
@@ -0,0 +1,41 @@
+//! Builds `solve.c` against the shared library and runs it.
+
+use std::env;
+use std::path::PathBuf;
+use std::process::Command;
+
+#[test]
+fn c_program() {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    // The test binary lives in `target/<profile>/deps`, next to the library's directory.
+    let lib_dir = env::current_exe()
+        .expect("Should have a path!")
+        .parent()
+        .and_then(|deps| deps.parent())
+        .expect("Should be in a target directory!")
+        .to_path_buf();
+    let exe = lib_dir.join("fcs_solve_test");
+
+    let status = Command::new(env::var("CC").unwrap_or_else(|_| "cc".to_string()))
+        .arg(dir.join("tests").join("solve.c"))
+        .arg("-I")
+        .arg(dir.join("include"))
+        .arg("-L")
+        .arg(&lib_dir)
+        .args(["-lfcs", "-o"])
+        .arg(&exe)
+        .status()
+        .expect("C compiler should be available!");
+    assert!(status.success());
+
+    let output = Command::new(&exe)
+        .env("LD_LIBRARY_PATH", &lib_dir)
+        .output()
+        .expect("Should be run!");
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(String::from_utf8_lossy(&output.stdout).starts_with("Deal 173205951 solved"));
+}
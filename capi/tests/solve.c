@@ -0,0 +1,72 @@
+/* Solves deal 173205951, both by its number and given as a board, and checks the solutions agree.
+ * Build and run in the repository root after `cargo build -p freecell-solver-capi`:
+ *   cc capi/tests/solve.c -Icapi/include -Ltarget/debug -lfcs -o solve && LD_LIBRARY_PATH=target/debug ./solve
+ */
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include "fcs.h"
+
+/* Deal 173205951, one pile per line. */
+static const char *BOARD =
+    "3H QD KC TH JC QC 6C\n"
+    "9S 5S JS TS 7H TD TC\n"
+    "KS 2C 2H 7D 9D JD 9C\n"
+    "7S 3C AD 3D 6D 6H 8C\n"
+    "KD QH 4C JH KH AS\n"
+    "6S 8S 2D 4H 8D 8H\n"
+    "4S AC 4D 5D 5H 7C\n"
+    "2S QS 5C AH 3S 9H\n";
+
+static fcs_move *result_moves(const fcs_solver *solver, size_t *len) {
+    *len = fcs_result_moves(solver, NULL, 0);
+    fcs_move *moves = malloc(*len * sizeof(fcs_move));
+    if (*len == 0 || moves == NULL || fcs_result_moves(solver, moves, *len) != *len) {
+        free(moves);
+        return NULL;
+    }
+    return moves;
+}
+
+int main(void) {
+    fcs_solver *solver = fcs_solver_new();
+    size_t deal_len, board_len;
+
+    if (fcs_solve_deal(solver, 173205951, 0) != FCS_SOLVED) {
+        fprintf(stderr, "Deal not solved\n");
+        return 1;
+    }
+    fcs_move *deal_moves = result_moves(solver, &deal_len);
+    if (deal_moves == NULL) {
+        fprintf(stderr, "Deal moves not copied\n");
+        return 1;
+    }
+    printf("Deal 173205951 solved in %zu moves:", deal_len);
+    for (size_t i = 0; i < deal_len; i++) {
+        printf(" %d->%d", deal_moves[i].giver, deal_moves[i].taker);
+    }
+    printf("\n");
+
+    if (fcs_solve_board(solver, BOARD, 0) != FCS_SOLVED) {
+        fprintf(stderr, "Board not solved\n");
+        return 1;
+    }
+    fcs_move *board_moves = result_moves(solver, &board_len);
+    if (board_moves == NULL || board_len != deal_len
+        || memcmp(board_moves, deal_moves, deal_len * sizeof(fcs_move)) != 0) {
+        fprintf(stderr, "Board solution differs from the deal one\n");
+        return 1;
+    }
+
+    if (fcs_solve_board(solver, "not a board", 0) != FCS_INVALID
+        || fcs_result_moves(solver, NULL, 0) != 0) {
+        fprintf(stderr, "Invalid board accepted\n");
+        return 1;
+    }
+
+    free(board_moves);
+    free(deal_moves);
+    fcs_free(solver);
+    return 0;
+}
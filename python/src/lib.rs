@@ -0,0 +1,270 @@
+//! # Python bindings
+//! The `freecell` module: deals, game positions and the solver.
+//! Moves and paths are hex strings, as used in the demo links (e.g. "7c", "4871317c").
+//!
+//! Build with `maturin develop` and test with `pytest` in this directory.
+
+use std::convert::TryFrom;
+use std::time::{Duration, Instant};
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use freecell_solver::deck;
+use freecell_solver::freecell::{self as fc, path_from_hex, path_to_hex, Move, CELL_NUM};
+
+fn parse_move(mv: &str) -> PyResult<Move> {
+    match path_from_hex(mv) {
+        Some(path) if path.len() == 1 => Ok(path[0].clone()),
+        _ => Err(PyValueError::new_err(format!("invalid move: '{}'", mv))),
+    }
+}
+
+/// Returns the cards of the deal, e.g. ["K♦", "3♠", ...].
+/// Cards are dealt into the piles from left to right, row by row.
+#[pyfunction]
+fn deal(seed: u64) -> Vec<String> {
+    deck::deal(seed)
+        .iter()
+        .map(|card| card.to_string())
+        .collect()
+}
+
+/// A game position with the path leading to it.
+#[pyclass]
+#[derive(Clone)]
+struct Game {
+    inner: fc::Game,
+}
+
+#[pymethods]
+impl Game {
+    #[new]
+    #[pyo3(signature = (seed, cells = CELL_NUM))]
+    fn new(seed: u64, cells: usize) -> Self {
+        let mut inner = fc::Game::new();
+        inner.set_cell_num(cells);
+        inner.deal(&deck::deal(seed));
+        Self { inner }
+    }
+
+    /// The number of cells available for play.
+    #[getter]
+    fn cells(&self) -> usize {
+        self.inner.cell_num()
+    }
+
+    /// The hex path from the deal.
+    #[getter]
+    fn path(&self) -> String {
+        path_to_hex(self.inner.path())
+    }
+
+    /// Returns the available moves.
+    fn moves(&self) -> Vec<String> {
+        self.inner
+            .get_all_moves()
+            .into_iter()
+            .map(|mv| path_to_hex(&vec![mv]))
+            .collect()
+    }
+
+    fn is_valid(&self, mv: &str) -> PyResult<bool> {
+        let mv = parse_move(mv)?;
+        Ok(self
+            .inner
+            .is_valid_move(mv.giver().index(), mv.taker().index()))
+    }
+
+    /// Makes a move, raising `ValueError` if it's illegal.
+    fn apply(&mut self, mv: &str) -> PyResult<()> {
+        if !self.is_valid(mv)? {
+            return Err(PyValueError::new_err(format!("illegal move: '{}'", mv)));
+        }
+        self.inner.apply(&parse_move(mv)?);
+        Ok(())
+    }
+
+    /// Moves cards to bases while it's safe. Returns the number of moved cards.
+    fn auto(&mut self) -> usize {
+        self.inner.move_cards_auto()
+    }
+
+    /// Takes back the last moves, keeping the first `mark` ones.
+    #[pyo3(signature = (mark = 0))]
+    fn backward(&mut self, mark: usize) {
+        self.inner.backward(mark);
+    }
+
+    fn is_done(&self) -> bool {
+        self.inner.is_done()
+    }
+
+    /// The number of cards lying on top of a lower card of the same suit.
+    fn count_locks(&self) -> usize {
+        self.inner.count_locks()
+    }
+
+    /// The lower bound of the solution length, including the path.
+    fn estimate_path_len(&self) -> usize {
+        self.inner.estimate_path_len()
+    }
+
+    /// Renders the board.
+    fn board(&self) -> String {
+        self.inner.to_string()
+    }
+
+    fn __str__(&self) -> String {
+        self.board()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Game(path='{}')", self.path())
+    }
+}
+
+/// The search limits, see `Limits` of the Rust crate.
+#[pyclass]
+#[derive(Clone)]
+struct Limits {
+    #[pyo3(get, set)]
+    path_max: usize,
+    #[pyo3(get, set)]
+    grab_max: usize,
+    #[pyo3(get, set)]
+    done_max: usize,
+    #[pyo3(get, set)]
+    any: bool,
+    /// The maximum time in seconds, or `None`.
+    #[pyo3(get, set)]
+    time_max: Option<f64>,
+}
+
+#[pymethods]
+impl Limits {
+    /// Omitted limits get the default values. Raises `ValueError` for a negative or non-finite time.
+    #[new]
+    #[pyo3(signature = (path_max = None, grab_max = None, done_max = None, any = None, time_max = None))]
+    fn new(
+        path_max: Option<usize>,
+        grab_max: Option<usize>,
+        done_max: Option<usize>,
+        any: Option<bool>,
+        time_max: Option<f64>,
+    ) -> PyResult<Self> {
+        let defaults = fc::Limits::default();
+        let limits = Self {
+            path_max: path_max.unwrap_or(defaults.path_max),
+            grab_max: grab_max.unwrap_or(defaults.grab_max),
+            done_max: done_max.unwrap_or(defaults.done_max),
+            any: any.unwrap_or(defaults.any),
+            time_max: time_max.or_else(|| defaults.time_max.map(|time| time.as_secs_f64())),
+        };
+        fc::Limits::try_from(&limits)?;
+        Ok(limits)
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Limits(path_max={}, grab_max={}, done_max={}, any={}, time_max={})",
+            self.path_max,
+            self.grab_max,
+            self.done_max,
+            if self.any { "True" } else { "False" },
+            self.time_max
+                .map_or("None".to_string(), |time| time.to_string())
+        )
+    }
+}
+
+/// Fails with `ValueError` if the time is negative or not finite, which the attribute setter lets through.
+impl TryFrom<&Limits> for fc::Limits {
+    type Error = PyErr;
+
+    fn try_from(limits: &Limits) -> PyResult<Self> {
+        let time_max = limits
+            .time_max
+            .map(|time| {
+                Duration::try_from_secs_f64(time)
+                    .map_err(|_| PyValueError::new_err(format!("invalid time_max: {}", time)))
+            })
+            .transpose()?;
+        Ok(Self {
+            path_max: limits.path_max,
+            grab_max: limits.grab_max,
+            done_max: limits.done_max,
+            any: limits.any,
+            time_max,
+        })
+    }
+}
+
+/// The search result.
+#[pyclass(get_all)]
+struct Solution {
+    /// The moves of the shortest path found, empty if not solved.
+    moves: Vec<String>,
+    /// The same moves as one hex path.
+    path: String,
+    solved: bool,
+    /// The number of processed positions.
+    done: usize,
+    /// The number of positions left unprocessed.
+    bank: usize,
+    /// The search time in seconds.
+    elapsed: f64,
+}
+
+#[pymethods]
+impl Solution {
+    fn __repr__(&self) -> String {
+        format!(
+            "Solution(solved={}, len={}, done={}, bank={}, elapsed={:.3})",
+            if self.solved { "True" } else { "False" },
+            self.moves.len(),
+            self.done,
+            self.bank,
+            self.elapsed
+        )
+    }
+}
+
+/// Solves the deal. The GIL is released during the search.
+#[pyfunction]
+#[pyo3(signature = (seed, limits = None, cells = CELL_NUM))]
+fn solve(py: Python<'_>, seed: u64, limits: Option<Limits>, cells: usize) -> PyResult<Solution> {
+    let limits = limits
+        .as_ref()
+        .map(fc::Limits::try_from)
+        .transpose()?
+        .unwrap_or_default();
+    Ok(py.allow_threads(|| {
+        let start = Instant::now();
+        let mut solver = fc::Solver::new();
+        solver.set_cell_num(cells);
+        solver.deal(seed);
+        let path = solver.solve(&limits).cloned().unwrap_or_default();
+        Solution {
+            moves: path
+                .iter()
+                .map(|mv| path_to_hex(&vec![mv.clone()]))
+                .collect(),
+            path: path_to_hex(&path),
+            solved: solver.path().is_some(),
+            done: solver.done().len(),
+            bank: solver.bank().len(),
+            elapsed: start.elapsed().as_secs_f64(),
+        }
+    }))
+}
+
+#[pymodule]
+fn freecell(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(deal, m)?)?;
+    m.add_function(wrap_pyfunction!(solve, m)?)?;
+    m.add_class::<Game>()?;
+    m.add_class::<Limits>()?;
+    m.add_class::<Solution>()?;
+    Ok(())
+}
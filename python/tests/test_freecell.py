@@ -0,0 +1,81 @@
+import pytest
+
+import freecell
+
+DEAL = 173205951
+
+
+def test_deal():
+    cards = freecell.deal(DEAL)
+    assert len(cards) == 52
+    assert len(set(cards)) == 52
+    assert cards == freecell.deal(DEAL)
+    assert cards != freecell.deal(DEAL + 1)
+
+
+def test_game():
+    game = freecell.Game(DEAL)
+    assert game.cells == 4
+    assert game.path == ""
+    assert "48" in game.moves()
+    assert game.is_valid("48")
+    assert not game.is_valid("c7")
+    assert not game.is_done()
+    assert game.count_locks() > 0
+    assert game.estimate_path_len() >= 52
+
+    game.apply("48")
+    game.apply("7c")
+    assert game.path == "487c"
+    with pytest.raises(ValueError):
+        game.apply("48")
+    with pytest.raises(ValueError):
+        game.apply("zz")
+
+    game.backward(1)
+    assert game.path == "48"
+    assert str(game) == game.board()
+    assert "|" in game.board()
+
+
+def test_cells():
+    game = freecell.Game(DEAL, cells=1)
+    assert game.cells == 1
+    game.apply("4c")
+    assert not game.is_valid("7d")
+
+
+def test_limits():
+    limits = freecell.Limits(done_max=1000, any=True)
+    assert limits.done_max == 1000
+    assert limits.any
+    assert limits.time_max is None
+    limits.time_max = 0.5
+    assert limits.time_max == 0.5
+
+    for time_max in [-1.0, float("nan"), float("inf")]:
+        with pytest.raises(ValueError):
+            freecell.Limits(time_max=time_max)
+        limits.time_max = time_max
+        with pytest.raises(ValueError):
+            freecell.solve(DEAL, limits)
+
+
+def test_solve():
+    solution = freecell.solve(DEAL, freecell.Limits(any=True, done_max=100000))
+    assert solution.solved
+    assert solution.path == "".join(solution.moves)
+    assert solution.done > 0
+    assert solution.elapsed >= 0
+
+    game = freecell.Game(DEAL)
+    for mv in solution.moves:
+        game.apply(mv)
+    assert game.is_done()
+
+
+def test_unsolved():
+    solution = freecell.solve(DEAL, freecell.Limits(done_max=0), cells=0)
+    assert not solution.solved
+    assert solution.moves == []
+    assert solution.path == ""
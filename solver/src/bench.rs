@@ -0,0 +1,91 @@
+//! Beam search benchmark: compares [`Beam`] with the default [`Solver`] on a range of deals.
+
+use std::ops::Range;
+use std::time::{Duration, Instant};
+
+use freecell_solver::deck;
+use freecell_solver::freecell::{Beam, Game, Limits, Solver};
+
+/// The results of a search method on the deals.
+#[derive(Default)]
+struct Tally {
+    solved: usize,
+    moves: usize,
+    time: Duration,
+}
+
+impl Tally {
+    fn add(&mut self, len: Option<usize>, time: Duration) {
+        if let Some(len) = len {
+            self.solved += 1;
+            self.moves += len;
+        }
+        self.time += time;
+    }
+
+    fn print(&self, name: &str, deals: usize) {
+        let average = if self.solved > 0 {
+            self.moves as f64 / self.solved as f64
+        } else {
+            0.0
+        };
+        println!(
+            "{}: solved {}/{}, average path {:.2}, time {:?}",
+            name, self.solved, deals, average, self.time
+        );
+    }
+}
+
+/// Solves each deal both ways, printing a line per deal and the totals.
+pub fn run(deals: Range<u64>, limits: &Limits, beam: &Beam) {
+    let mut solver_tally = Tally::default();
+    let mut beam_tally = Tally::default();
+    // Path lengths of the deals solved both ways.
+    let mut both = (0, 0, 0);
+
+    println!("deal\tsolver\tms\tbeam\twidth\tms");
+    for deal in deals.clone() {
+        let mut game = Game::new();
+        game.deal(&deck::deal(deal));
+
+        let clock = Instant::now();
+        let mut solver = Solver::new();
+        solver.start_from(&game);
+        let solver_len = solver.solve(limits).map(Vec::len);
+        let solver_time = clock.elapsed();
+        solver_tally.add(solver_len, solver_time);
+
+        let clock = Instant::now();
+        let found = beam.solve(&game);
+        let beam_time = clock.elapsed();
+        let beam_len = found.as_ref().map(|(path, _)| path.len());
+        beam_tally.add(beam_len, beam_time);
+
+        if let (Some(solver_len), Some(beam_len)) = (solver_len, beam_len) {
+            both = (both.0 + 1, both.1 + solver_len, both.2 + beam_len);
+        }
+        let len = |len: Option<usize>| len.map_or("-".to_string(), |len| len.to_string());
+        println!(
+            "{}\t{}\t{}\t{}\t{}\t{}",
+            deal,
+            len(solver_len),
+            solver_time.as_millis(),
+            len(beam_len),
+            len(found.map(|(_, width)| width)),
+            beam_time.as_millis()
+        );
+    }
+
+    let deals = (deals.end - deals.start) as usize;
+    println!();
+    solver_tally.print("Solver", deals);
+    beam_tally.print("Beam", deals);
+    if both.0 > 0 {
+        println!(
+            "Solved both ways: {}, average path: solver {:.2}, beam {:.2}",
+            both.0,
+            both.1 as f64 / both.0 as f64,
+            both.2 as f64 / both.0 as f64
+        );
+    }
+}
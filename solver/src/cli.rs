@@ -0,0 +1,709 @@
+//! Command line interface: the arguments, their defaults and validators.
+
+use clap::{App, AppSettings, Arg, ArgGroup, SubCommand};
+use std::str::FromStr;
+
+use freecell_solver::freecell::{path_from_hex, AutoPlay, MoveOrder};
+
+/// The names of the arguments and subcommands.
+pub mod arg {
+    pub const DEAL: &str = "deal";
+    pub const PATH_MAX: &str = "path-max";
+    pub const GRAB_MAX: &str = "grab-max";
+    pub const DONE_MAX: &str = "done-max";
+    pub const VERBOSE: &str = "verbose";
+    pub const ANY: &str = "any";
+    pub const CELLS: &str = "cells";
+    pub const MIN_CELLS: &str = "min-cells";
+    pub const FROM: &str = "from";
+    pub const CACHE: &str = "cache";
+    pub const FILE: &str = "file";
+    pub const IMPORT: &str = "import";
+    pub const EXPORT: &str = "export";
+    pub const IMPROVEMENTS: &str = "improvements";
+    pub const GENERATE: &str = "generate";
+    pub const COUNT: &str = "count";
+    pub const START: &str = "start";
+    pub const SAMPLE: &str = "sample";
+    pub const PERMUTATIONS: &str = "permutations";
+    pub const RNG: &str = "rng";
+    pub const TRIES: &str = "tries";
+    pub const SOLVABLE_CELLS: &str = "solvable-cells";
+    pub const MIN_LEN: &str = "min-len";
+    pub const MIN_LOCKS: &str = "min-locks";
+    pub const NO_BOTTOM_ACES: &str = "no-bottom-aces";
+    pub const BOARD: &str = "board";
+    pub const PLAY: &str = "play";
+    pub const TIME_MAX: &str = "time-max";
+    pub const SERVE: &str = "serve";
+    pub const JOBS: &str = "jobs";
+    pub const GRAPH: &str = "graph";
+    pub const DEPTH: &str = "depth";
+    pub const SOLUTION: &str = "solution";
+    pub const SOLVE: &str = "solve";
+    pub const JSON: &str = "json";
+    pub const TRACE: &str = "trace";
+    pub const STATS: &str = "stats";
+    pub const NDJSON: &str = "ndjson";
+    pub const BEAM: &str = "beam";
+    pub const WIDEN: &str = "widen";
+    pub const WIDTH_MAX: &str = "width-max";
+    pub const BENCH: &str = "bench";
+    pub const PORTFOLIO: &str = "portfolio";
+    pub const CONFIGS: &str = "configs";
+    pub const IN_TURNS: &str = "in-turns";
+    pub const SLICE: &str = "slice";
+    pub const ORDER: &str = "order";
+    pub const AUTO: &str = "auto";
+    #[cfg(feature = "http")]
+    pub const HTTP: &str = "http";
+    #[cfg(feature = "http")]
+    pub const TIMEOUT: &str = "timeout";
+}
+
+fn is_unsigned<T: FromStr>(v: String) -> Result<(), String> {
+    match v.parse::<T>() {
+        Err(_) => Err(format!(
+            "should be a non-negative integer value, but got '{}'.",
+            v
+        )),
+        Ok(_) => Ok(()),
+    }
+}
+
+fn is_hex_path(v: String) -> Result<(), String> {
+    match path_from_hex(&v) {
+        None => Err(format!(
+            "should be a sequence of hex digit pairs, but got '{}'.",
+            v
+        )),
+        Some(_) => Ok(()),
+    }
+}
+
+fn is_auto_play(v: String) -> Result<(), String> {
+    match v.parse::<AutoPlay>() {
+        Err(_) => Err(format!(
+            "should be 'none', 'standard', 'microsoft', 'horne' or 'aggressive', but got '{}'.",
+            v
+        )),
+        Ok(_) => Ok(()),
+    }
+}
+
+fn is_move_order(v: String) -> Result<(), String> {
+    match v.parse::<MoveOrder>() {
+        Err(_) => Err(format!(
+            "should be 'standard', 'score', 'home' or 'random:SEED', but got '{}'.",
+            v
+        )),
+        Ok(_) => Ok(()),
+    }
+}
+
+pub struct DefaultParam<T> {
+    pub value: T,
+    pub name: &'static str,
+}
+
+macro_rules! define_param {
+    ( $name:ident : $t:ty = $val:expr ) => {
+        pub const $name: DefaultParam<$t> = DefaultParam {
+            value: $val,
+            name: stringify!($val),
+        };
+    };
+}
+
+define_param!(DEAL: u64 = 0);
+define_param!(PATH_MAX: usize = 256);
+define_param!(GRAB_MAX: usize = 1000);
+define_param!(DONE_MAX: usize = 10000000);
+define_param!(CELLS: usize = 4);
+define_param!(HINT_TIME: u64 = 5000);
+#[cfg(feature = "http")]
+define_param!(TIMEOUT: u64 = 10000);
+define_param!(COUNT: usize = 10);
+define_param!(RNG: u64 = 0);
+define_param!(FILTER_DONE_MAX: usize = 100000);
+define_param!(GRAPH_DEPTH: usize = 2);
+define_param!(BEAM_WIDTH: usize = 100);
+define_param!(BEAM_WIDEN: usize = 4);
+define_param!(BEAM_WIDTH_MAX: usize = 10000);
+define_param!(CONFIGS: usize = 4);
+define_param!(SLICE: usize = 10000);
+
+/// Returns the command line interface.
+pub fn app() -> App<'static, 'static> {
+    let serve_command = SubCommand::with_name(arg::SERVE)
+        .about("Serves newline-delimited JSON requests on stdin (solve, verify, hint, rate)")
+        .arg(
+            Arg::with_name(arg::JOBS)
+                .help("The number of requests to be processed concurrently [default: CPU count]")
+                .short("j")
+                .long("jobs")
+                .required(false)
+                .takes_value(true)
+                .value_name("NUMBER")
+                .validator(is_unsigned::<usize>),
+        );
+    #[cfg(feature = "http")]
+    let serve_command = serve_command
+        .arg(
+            Arg::with_name(arg::HTTP)
+                .help("Serves HTTP requests on the address instead of stdin")
+                .long("http")
+                .required(false)
+                .takes_value(true)
+                .value_name("ADDR"),
+        )
+        .arg(
+            Arg::with_name(arg::TIMEOUT)
+                .help("The maximum time in milliseconds to be spent for an HTTP request")
+                .short("T")
+                .long("timeout")
+                .required(false)
+                .takes_value(true)
+                .default_value(TIMEOUT.name)
+                .value_name("NUMBER")
+                .validator(is_unsigned::<u64>),
+        );
+
+    App::new("FreeCell Solver")
+        .version(crate_version!())
+        .about("Solves FreeCell solitaries for [https://constf1.github.io/angular/freecell-demo]")
+        .setting(AppSettings::SubcommandsNegateReqs)
+        .setting(AppSettings::ArgsNegateSubcommands)
+        // Regular App configuration goes here...
+        .arg(
+            Arg::with_name(arg::DEAL)
+                .help("The deal number to use") // Displayed when showing help info.
+                .index(1) // Set the order in which the user must specify this argument.
+                .required(true) // By default this argument MUST be present.
+                .value_name("NUMBER")
+                .validator(is_unsigned::<u64>), // It should be a non-negative integer value.
+        )
+        .arg(
+            Arg::with_name(arg::PATH_MAX)
+                .help("The upper bound of the search range (inclusive)")
+                .short("P")
+                .long("path")
+                .required(false)
+                .takes_value(true)
+                .default_value(PATH_MAX.name)
+                .value_name("NUMBER")
+                .validator(is_unsigned::<usize>),
+        )
+        .arg(
+            Arg::with_name(arg::GRAB_MAX)
+                .help("The maximum number of variants to be processed in one iteration")
+                .short("S")
+                .long("scoop")
+                .required(false)
+                .takes_value(true)
+                .default_value(GRAB_MAX.name)
+                .value_name("NUMBER")
+                .validator(is_unsigned::<usize>),
+        )
+        .arg(
+            Arg::with_name(arg::DONE_MAX)
+                .help("The maximum number of variants to be processed in total")
+                .short("L")
+                .long("limit")
+                .required(false)
+                .takes_value(true)
+                .default_value(DONE_MAX.name)
+                .value_name("NUMBER")
+                .validator(is_unsigned::<usize>),
+        )
+        .arg(
+            Arg::with_name(arg::TIME_MAX)
+                .help("The maximum time in milliseconds to be spent for the search")
+                .short("T")
+                .long("time")
+                .required(false)
+                .takes_value(true)
+                .value_name("NUMBER")
+                .validator(is_unsigned::<u64>)
+                .conflicts_with(arg::BEAM),
+        )
+        .arg(
+            Arg::with_name(arg::VERBOSE)
+                .help("Use debug output")
+                .short("D")
+                .long("debug")
+                .alias("verbose")
+                .required(false),
+        )
+        .arg(
+            Arg::with_name(arg::ANY)
+                .help("Stop on the first result")
+                .short("A")
+                .long("any")
+                .required(false),
+        )
+        .arg(
+            Arg::with_name(arg::CELLS)
+                .help("The number of free cells available for play")
+                .short("C")
+                .long("cells")
+                .required(false)
+                .takes_value(true)
+                .default_value(CELLS.name)
+                .value_name("NUMBER")
+                .validator(is_unsigned::<usize>),
+        )
+        .arg(
+            Arg::with_name(arg::MIN_CELLS)
+                .help("Find the smallest number of free cells the deal can be solved with")
+                .short("M")
+                .long("min-cells")
+                .required(false)
+                .conflicts_with(arg::CELLS),
+        )
+        .arg(
+            Arg::with_name(arg::FROM)
+                .help("The hex path to reach the starting position from the deal")
+                .short("F")
+                .long("from")
+                .required(false)
+                .takes_value(true)
+                .value_name("PATH")
+                .validator(is_hex_path)
+                .conflicts_with(arg::MIN_CELLS),
+        )
+        .arg(
+            Arg::with_name(arg::CACHE)
+                .help("The solution cache file to be consulted before searching and updated with shorter paths")
+                .short("K")
+                .long("cache")
+                .required(false)
+                .takes_value(true)
+                .value_name("FILE")
+                .conflicts_with(arg::MIN_CELLS),
+        )
+        .arg(
+            Arg::with_name(arg::STATS)
+                .help("Prints the search statistics")
+                .long("stats")
+                .required(false)
+                .conflicts_with(arg::MIN_CELLS),
+        )
+        .arg(
+            Arg::with_name(arg::NDJSON)
+                .help("Prints each shorter solution as soon as it's found as a JSON line")
+                .long("ndjson")
+                .required(false)
+                .conflicts_with_all(&[arg::MIN_CELLS, arg::VERBOSE, arg::STATS]),
+        )
+        .arg(
+            Arg::with_name(arg::BEAM)
+                .help("Uses beam search keeping this number of positions per depth, widened on failure")
+                .short("B")
+                .long("beam")
+                .required(false)
+                .takes_value(true)
+                .value_name("WIDTH")
+                .validator(is_unsigned::<usize>)
+                .conflicts_with_all(&[arg::MIN_CELLS, arg::CACHE, arg::TRACE, arg::STATS, arg::NDJSON]),
+        )
+        .arg(
+            Arg::with_name(arg::ORDER)
+                .help("The order the moves of each position are tried in: standard, score (best resulting positions first), home (cards going home soon exposed first) or random:SEED")
+                .long("order")
+                .required(false)
+                .takes_value(true)
+                .value_name("ORDER")
+                .validator(is_move_order)
+                .conflicts_with_all(&[arg::MIN_CELLS, arg::BEAM]),
+        )
+        .arg(
+            Arg::with_name(arg::AUTO)
+                .help("Which cards go to bases automatically: none, standard, microsoft, horne (the safe ones keep the shortest solutions reachable) or aggressive")
+                .long("auto")
+                .required(false)
+                .takes_value(true)
+                .value_name("POLICY")
+                .validator(is_auto_play)
+                .conflicts_with(arg::MIN_CELLS),
+        )
+        .arg(
+            Arg::with_name(arg::TRACE)
+                .help("Records the positions generated by the search in the file, one JSON line each")
+                .long("trace")
+                .required(false)
+                .takes_value(true)
+                .value_name("FILE")
+                .conflicts_with(arg::MIN_CELLS),
+        )
+        .subcommand(
+            SubCommand::with_name(arg::PLAY)
+                .about("Plays a deal interactively in the terminal")
+                .arg(
+                    Arg::with_name(arg::DEAL)
+                        .help("The deal number to use")
+                        .index(1)
+                        .required(true)
+                        .value_name("NUMBER")
+                        .validator(is_unsigned::<u64>),
+                )
+                .arg(
+                    Arg::with_name(arg::DONE_MAX)
+                        .help("The maximum number of variants to be processed for a hint")
+                        .short("L")
+                        .long("limit")
+                        .required(false)
+                        .takes_value(true)
+                        .default_value(DONE_MAX.name)
+                        .value_name("NUMBER")
+                        .validator(is_unsigned::<usize>),
+                )
+                .arg(
+                    Arg::with_name(arg::TIME_MAX)
+                        .help("The maximum time in milliseconds to be spent for a hint")
+                        .short("T")
+                        .long("time")
+                        .required(false)
+                        .takes_value(true)
+                        .default_value(HINT_TIME.name)
+                        .value_name("NUMBER")
+                        .validator(is_unsigned::<u64>),
+                ),
+        )
+        .subcommand(serve_command)
+        .subcommand(
+            SubCommand::with_name(arg::GENERATE)
+                .about("Generates deals matching all given properties")
+                .arg(
+                    Arg::with_name(arg::COUNT)
+                        .help("The number of deals to be generated")
+                        .short("n")
+                        .long("count")
+                        .takes_value(true)
+                        .default_value(COUNT.name)
+                        .value_name("NUMBER")
+                        .validator(is_unsigned::<usize>),
+                )
+                .arg(
+                    Arg::with_name(arg::START)
+                        .help("Tests consecutive deal numbers starting with this one [default]")
+                        .long("start")
+                        .takes_value(true)
+                        .value_name("NUMBER")
+                        .validator(is_unsigned::<u64>),
+                )
+                .arg(
+                    Arg::with_name(arg::SAMPLE)
+                        .help("Tests random deal numbers")
+                        .long("sample"),
+                )
+                .arg(
+                    Arg::with_name(arg::PERMUTATIONS)
+                        .help("Tests random permutations of the deck, printed as card strings")
+                        .long("permutations"),
+                )
+                .group(ArgGroup::with_name("source").args(&[arg::START, arg::SAMPLE, arg::PERMUTATIONS]))
+                .arg(
+                    Arg::with_name(arg::RNG)
+                        .help("The seed of the random number generator, the same seed gives the same deals")
+                        .long("rng")
+                        .takes_value(true)
+                        .default_value(RNG.name)
+                        .value_name("NUMBER")
+                        .validator(is_unsigned::<u64>),
+                )
+                .arg(
+                    Arg::with_name(arg::TRIES)
+                        .help("The maximum number of deals to be tested")
+                        .long("tries")
+                        .takes_value(true)
+                        .value_name("NUMBER")
+                        .validator(is_unsigned::<usize>),
+                )
+                .arg(
+                    Arg::with_name(arg::SOLVABLE_CELLS)
+                        .help("Keeps deals solvable with this number of cells")
+                        .long("solvable-with")
+                        .takes_value(true)
+                        .value_name("NUMBER")
+                        .validator(is_unsigned::<usize>),
+                )
+                .arg(
+                    Arg::with_name(arg::MIN_LEN)
+                        .help("Keeps deals whose shortest found solution has at least this number of moves")
+                        .long("min-len")
+                        .takes_value(true)
+                        .value_name("NUMBER")
+                        .validator(is_unsigned::<usize>),
+                )
+                .arg(
+                    Arg::with_name(arg::MIN_LOCKS)
+                        .help("Keeps deals with at least this number of locked cards")
+                        .long("min-locks")
+                        .takes_value(true)
+                        .value_name("NUMBER")
+                        .validator(is_unsigned::<usize>),
+                )
+                .arg(
+                    Arg::with_name(arg::NO_BOTTOM_ACES)
+                        .help("Keeps deals without aces in the bottom row")
+                        .long("no-bottom-aces"),
+                )
+                .arg(
+                    Arg::with_name(arg::DONE_MAX)
+                        .help("The maximum number of variants to be processed by each solver check")
+                        .short("L")
+                        .long("limit")
+                        .takes_value(true)
+                        .default_value(FILTER_DONE_MAX.name)
+                        .value_name("NUMBER")
+                        .validator(is_unsigned::<usize>),
+                )
+                .arg(
+                    Arg::with_name(arg::BOARD)
+                        .help("Prints the cards of numbered deals too")
+                        .long("board"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name(arg::CACHE)
+                .about("Manages a solution cache file")
+                .arg(
+                    Arg::with_name(arg::FILE)
+                        .help("The cache file")
+                        .index(1)
+                        .required(true)
+                        .value_name("FILE"),
+                )
+                .arg(
+                    Arg::with_name(arg::IMPORT)
+                        .help("Imports the paths of another cache file which improve on the known ones")
+                        .long("import")
+                        .takes_value(true)
+                        .value_name("FILE"),
+                )
+                .arg(
+                    Arg::with_name(arg::EXPORT)
+                        .help("Prints the best known paths")
+                        .long("export"),
+                )
+                .arg(
+                    Arg::with_name(arg::IMPROVEMENTS)
+                        .help("Lists the improvements in the order they were found")
+                        .long("improvements"),
+                )
+                .group(
+                    ArgGroup::with_name("action")
+                        .args(&[arg::IMPORT, arg::EXPORT, arg::IMPROVEMENTS])
+                        .required(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name(arg::TRACE)
+                .about("Summarises a search trace recorded with --trace")
+                .arg(
+                    Arg::with_name(arg::FILE)
+                        .help("The trace file")
+                        .index(1)
+                        .required(true)
+                        .value_name("FILE"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name(arg::BENCH)
+                .about("Compares beam search with the default search on a range of deals")
+                .arg(
+                    Arg::with_name(arg::START)
+                        .help("The first deal number")
+                        .index(1)
+                        .required(true)
+                        .value_name("NUMBER")
+                        .validator(is_unsigned::<u64>),
+                )
+                .arg(
+                    Arg::with_name(arg::COUNT)
+                        .help("The number of deals")
+                        .short("n")
+                        .long("count")
+                        .takes_value(true)
+                        .default_value(COUNT.name)
+                        .value_name("NUMBER")
+                        .validator(is_unsigned::<u64>),
+                )
+                .arg(
+                    Arg::with_name(arg::BEAM)
+                        .help("The number of positions kept per depth on the first try")
+                        .short("B")
+                        .long("beam")
+                        .takes_value(true)
+                        .default_value(BEAM_WIDTH.name)
+                        .value_name("WIDTH")
+                        .validator(is_unsigned::<usize>),
+                )
+                .arg(
+                    Arg::with_name(arg::WIDEN)
+                        .help("The factor the width is multiplied by after a failed try")
+                        .long("widen")
+                        .takes_value(true)
+                        .default_value(BEAM_WIDEN.name)
+                        .value_name("NUMBER")
+                        .validator(is_unsigned::<usize>),
+                )
+                .arg(
+                    Arg::with_name(arg::WIDTH_MAX)
+                        .help("The largest width to be tried")
+                        .long("width-max")
+                        .takes_value(true)
+                        .default_value(BEAM_WIDTH_MAX.name)
+                        .value_name("WIDTH")
+                        .validator(is_unsigned::<usize>),
+                )
+                .arg(
+                    Arg::with_name(arg::DONE_MAX)
+                        .help("The maximum number of variants to be processed by the default search")
+                        .short("L")
+                        .long("limit")
+                        .takes_value(true)
+                        .default_value(FILTER_DONE_MAX.name)
+                        .value_name("NUMBER")
+                        .validator(is_unsigned::<usize>),
+                )
+                .arg(
+                    Arg::with_name(arg::ANY)
+                        .help("Stops the default search on the first result")
+                        .short("A")
+                        .long("any"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name(arg::PORTFOLIO)
+                .about("Runs several solver configurations on a deal and reports the one which won")
+                .arg(
+                    Arg::with_name(arg::DEAL)
+                        .help("The deal number to use")
+                        .index(1)
+                        .required(true)
+                        .value_name("NUMBER")
+                        .validator(is_unsigned::<u64>),
+                )
+                .arg(
+                    Arg::with_name(arg::CONFIGS)
+                        .help("The number of configurations, the default one first")
+                        .short("n")
+                        .long("configs")
+                        .takes_value(true)
+                        .default_value(CONFIGS.name)
+                        .value_name("NUMBER")
+                        .validator(is_unsigned::<usize>),
+                )
+                .arg(
+                    Arg::with_name(arg::DONE_MAX)
+                        .help("The maximum number of variants to be processed by each configuration")
+                        .short("L")
+                        .long("limit")
+                        .takes_value(true)
+                        .default_value(FILTER_DONE_MAX.name)
+                        .value_name("NUMBER")
+                        .validator(is_unsigned::<usize>),
+                )
+                .arg(
+                    Arg::with_name(arg::ANY)
+                        .help("The first solution wins instead of the shortest one")
+                        .short("A")
+                        .long("any"),
+                )
+                .arg(
+                    Arg::with_name(arg::TIME_MAX)
+                        .help("The maximum time in milliseconds to be spent for the whole run")
+                        .short("T")
+                        .long("time")
+                        .takes_value(true)
+                        .value_name("NUMBER")
+                        .validator(is_unsigned::<u64>),
+                )
+                .arg(
+                    Arg::with_name(arg::IN_TURNS)
+                        .help("Runs the configurations in turns in one thread instead of concurrently")
+                        .long("in-turns"),
+                )
+                .arg(
+                    Arg::with_name(arg::SLICE)
+                        .help("The number of variants each configuration processes per turn with --in-turns")
+                        .long("slice")
+                        .takes_value(true)
+                        .default_value(SLICE.name)
+                        .value_name("NUMBER")
+                        .validator(is_unsigned::<usize>),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name(arg::GRAPH)
+                .about("Prints the move graph around a position in the Graphviz DOT language")
+                .arg(
+                    Arg::with_name(arg::DEAL)
+                        .help("The deal number to use")
+                        .index(1)
+                        .required(true)
+                        .value_name("NUMBER")
+                        .validator(is_unsigned::<u64>),
+                )
+                .arg(
+                    Arg::with_name(arg::DEPTH)
+                        .help("The number of player moves to be expanded")
+                        .short("d")
+                        .long("depth")
+                        .takes_value(true)
+                        .default_value(GRAPH_DEPTH.name)
+                        .value_name("NUMBER")
+                        .validator(is_unsigned::<usize>),
+                )
+                .arg(
+                    Arg::with_name(arg::FROM)
+                        .help("The hex path to reach the position from the deal")
+                        .short("F")
+                        .long("from")
+                        .takes_value(true)
+                        .value_name("PATH")
+                        .validator(is_hex_path),
+                )
+                .arg(
+                    Arg::with_name(arg::CELLS)
+                        .help("The number of free cells available for play")
+                        .short("C")
+                        .long("cells")
+                        .takes_value(true)
+                        .default_value(CELLS.name)
+                        .value_name("NUMBER")
+                        .validator(is_unsigned::<usize>),
+                )
+                .arg(
+                    Arg::with_name(arg::SOLUTION)
+                        .help("The hex path of a solution from the position to be marked")
+                        .long("solution")
+                        .takes_value(true)
+                        .value_name("PATH")
+                        .validator(is_hex_path),
+                )
+                .arg(
+                    Arg::with_name(arg::SOLVE)
+                        .help("Searches for a solution from the position to be marked")
+                        .long("solve")
+                        .conflicts_with(arg::SOLUTION),
+                )
+                .arg(
+                    Arg::with_name(arg::DONE_MAX)
+                        .help("The maximum number of variants to be processed by the search")
+                        .short("L")
+                        .long("limit")
+                        .takes_value(true)
+                        .default_value(FILTER_DONE_MAX.name)
+                        .value_name("NUMBER")
+                        .validator(is_unsigned::<usize>),
+                )
+                .arg(
+                    Arg::with_name(arg::JSON)
+                        .help("Prints the graph as JSON instead")
+                        .long("json"),
+                ),
+        )
+}
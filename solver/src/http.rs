@@ -0,0 +1,244 @@
+//! HTTP server mode.
+//!
+//! Serves the methods of the line protocol (see the `serve` module) as GET endpoints
+//! `/solve`, `/verify`, `/hint` and `/rate`, with the parameters in the query string, e.g.
+//! `/solve?deal=173205951&any=true&done_max=100000`.
+//!
+//! Query parameters: `deal` or `board`, `path`, `cells`, and the limits
+//! `path_max`, `grab_max`, `done_max`, `any` and `time_max` (in milliseconds).
+//! The server timeout runs from the arrival of a request: it caps the time spent in the queue
+//! and in all the searches of the method together, e.g. the runs with 0 to 4 cells of `/rate`.
+//!
+//! Requests are queued for a fixed number of workers. If the queue is full,
+//! the server answers "503 Service Unavailable" instead of waiting.
+
+use std::io;
+use std::sync::mpsc::{self, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde_json::{json, Value};
+use tiny_http::{Header, Request, Response, Server};
+
+use freecell_solver::freecell::CELL_NUM;
+
+use crate::serve::{call, GivenLimits, Method, Params};
+
+/// The number of queued requests per worker.
+const QUEUE_FACTOR: usize = 4;
+
+/// Decodes a percent-encoded query component, with '+' standing for a space.
+fn decode(text: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut iter = text.bytes();
+    while let Some(byte) = iter.next() {
+        bytes.push(match byte {
+            b'+' => b' ',
+            b'%' => {
+                let hex = [iter.next()?, iter.next()?];
+                u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?
+            }
+            byte => byte,
+        });
+    }
+    String::from_utf8(bytes).ok()
+}
+
+fn parse<T: std::str::FromStr>(key: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid value of '{}': '{}'", key, value))
+}
+
+/// Parses the query string into the method parameters. The search time is capped by the time left.
+fn params(query: &str, time_left: Duration) -> Result<Params, String> {
+    let mut params = Params {
+        deal: None,
+        board: None,
+        path: String::new(),
+        cells: CELL_NUM,
+        limits: GivenLimits::default(),
+        anytime: false,
+    };
+    for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+        let mut split = pair.splitn(2, '=');
+        let key = split.next().unwrap_or_default();
+        let value = decode(split.next().unwrap_or_default())
+            .ok_or_else(|| format!("malformed value of '{}'", key))?;
+        match key {
+            "deal" => params.deal = Some(parse(key, &value)?),
+            "board" => params.board = Some(value),
+            "path" => params.path = value,
+            "cells" => params.cells = parse(key, &value)?,
+            "path_max" => params.limits.path_max = Some(parse(key, &value)?),
+            "grab_max" => params.limits.grab_max = Some(parse(key, &value)?),
+            "done_max" => params.limits.done_max = Some(parse(key, &value)?),
+            "any" => params.limits.any = Some(parse(key, &value)?),
+            "time_max" => params.limits.time_max = Some(Duration::from_millis(parse(key, &value)?)),
+            _ => return Err(format!("unknown parameter: '{}'", key)),
+        }
+    }
+    params.limits.time_max = Some(
+        params
+            .limits
+            .time_max
+            .map_or(time_left, |time| time.min(time_left)),
+    );
+    Ok(params)
+}
+
+/// Returns the status code and the JSON body of the response to the URL, to be given by the deadline.
+fn handle(url: &str, deadline: Instant) -> (u16, Value) {
+    let (path, query) = match url.find('?') {
+        Some(index) => (&url[..index], &url[index + 1..]),
+        None => (url, ""),
+    };
+    let method: fn(Params) -> Method = match path {
+        "/solve" => Method::Solve,
+        "/verify" => Method::Verify,
+        "/hint" => Method::Hint,
+        "/rate" => Method::Rate,
+        _ => {
+            return (
+                404,
+                json!({ "error": format!("unknown endpoint: '{}'", path) }),
+            )
+        }
+    };
+    let time_left = deadline.saturating_duration_since(Instant::now());
+    match params(query, time_left).and_then(|params| call(&method(params), &mut |_| {})) {
+        Ok(result) => (200, result),
+        Err(error) => (400, json!({ "error": error })),
+    }
+}
+
+fn respond(request: Request, status: u16, body: &Value) {
+    let header = Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..])
+        .expect("Should be a valid header!");
+    let response = Response::from_string(body.to_string())
+        .with_status_code(status)
+        .with_header(header);
+    // The client may be gone, there's nobody to report to.
+    let _ = request.respond(response);
+}
+
+/// Serves requests with the given number of workers, each request being limited by the timeout.
+pub fn serve(server: Server, jobs: usize, timeout: Duration) {
+    let jobs = jobs.max(1);
+    let (sender, receiver) = mpsc::sync_channel::<(Request, Instant)>(jobs * QUEUE_FACTOR);
+    let receiver = Arc::new(Mutex::new(receiver));
+
+    for _ in 0..jobs {
+        let receiver = Arc::clone(&receiver);
+        thread::spawn(move || loop {
+            let (request, deadline) = match receiver.lock().expect("Should be locked!").recv() {
+                Ok(queued) => queued,
+                Err(_) => break,
+            };
+            let (status, body) = handle(request.url(), deadline);
+            respond(request, status, &body);
+        });
+    }
+
+    for request in server.incoming_requests() {
+        let deadline = Instant::now() + timeout;
+        if let Err(TrySendError::Full((request, _))) = sender.try_send((request, deadline)) {
+            respond(request, 503, &json!({ "error": "too many requests" }));
+        }
+    }
+}
+
+/// Binds the address and serves requests until the process is stopped.
+pub fn run(addr: &str, jobs: usize, timeout: Duration) -> io::Result<()> {
+    let server = Server::http(addr).map_err(|err| io::Error::other(err.to_string()))?;
+    serve(server, jobs, timeout);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::{SocketAddr, TcpStream};
+    use std::time::Instant;
+
+    fn start(jobs: usize, timeout: Duration) -> SocketAddr {
+        let server = Server::http("127.0.0.1:0").expect("Should be bound!");
+        let addr = server
+            .server_addr()
+            .to_ip()
+            .expect("Should be an IP address!");
+        thread::spawn(move || serve(server, jobs, timeout));
+        addr
+    }
+
+    fn get(addr: SocketAddr, url: &str) -> (u16, Value) {
+        let mut stream = TcpStream::connect(addr).expect("Should be connected!");
+        write!(stream, "GET {} HTTP/1.0\r\nHost: localhost\r\n\r\n", url).expect("Should be sent!");
+        let mut response = String::new();
+        stream
+            .read_to_string(&mut response)
+            .expect("Should be received!");
+        let status = response[9..12].parse().expect("Should be a status code!");
+        let body = &response[response.find("\r\n\r\n").expect("Should have a body!") + 4..];
+        (status, serde_json::from_str(body).expect("Should be JSON!"))
+    }
+
+    #[test]
+    fn endpoints() {
+        let addr = start(2, Duration::from_secs(10));
+
+        let (status, solved) = get(addr, "/solve?deal=173205951&any=true&done_max=100000");
+        assert_eq!(200, status);
+        assert_eq!(json!(true), solved["solved"]);
+        let path = solved["path"].as_str().expect("Should be a path!");
+        assert!(solved["link"]
+            .as_str()
+            .expect("Should be a link!")
+            .ends_with(&format!("deal=173205951&path={}", path)));
+
+        let (status, verified) = get(addr, &format!("/verify?deal=173205951&path={}", path));
+        assert_eq!((200, json!(true)), (status, verified["solved"].clone()));
+
+        let (status, hint) = get(
+            addr,
+            "/hint?deal=173205951&path=48&any=true&done_max=100000",
+        );
+        assert_eq!((200, json!(true)), (status, hint["winning"].clone()));
+
+        let (status, rated) = get(addr, "/rate?deal=1&any=true");
+        assert_eq!((200, json!(2)), (status, rated["cells"].clone()));
+
+        let (status, _) = get(addr, "/solve?deal=1&speed=5");
+        assert_eq!(400, status);
+        let (status, _) = get(addr, "/fly");
+        assert_eq!(404, status);
+    }
+
+    #[test]
+    fn timeouts() {
+        // Rating searches with each number of cells, the timeout is for all of them.
+        let timeout = Duration::from_millis(300);
+        let addr = start(1, timeout);
+        let start = Instant::now();
+        let (status, _) = get(addr, "/rate?deal=173205951&time_max=100000");
+        assert_eq!(200, status);
+        assert!(start.elapsed() < timeout * 2);
+    }
+
+    #[test]
+    fn queries() {
+        assert_eq!(
+            Some("A♠ 2♦\n".to_string()),
+            decode("A%E2%99%A0+2%E2%99%A6%0A")
+        );
+        assert_eq!(None, decode("%E"));
+
+        let parsed =
+            params("deal=1&time_max=500", Duration::from_millis(100)).expect("Should be parsed!");
+        assert_eq!(Some(1), parsed.deal);
+        assert_eq!(Some(Duration::from_millis(100)), parsed.limits.time_max);
+        assert!(params("deal=x", Duration::from_millis(100)).is_err());
+    }
+}
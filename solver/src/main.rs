@@ -1,24 +1,97 @@
 #[macro_use]
 extern crate clap;
-use clap::{App, Arg};
+use clap::ArgMatches;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
 use std::str::FromStr;
+use std::time::Duration;
 
+mod bench;
+mod cli;
+#[cfg(feature = "http")]
+mod http;
+mod play;
+mod serve;
+
+use cli::*;
+use freecell_solver::cache::{Cache, Key};
 use freecell_solver::deck;
-use freecell_solver::freecell::{spot_name, spot_to_hex, Game, Path, Solver};
+use freecell_solver::freecell::{
+    demo_link, locks_at_least, min_cells, no_aces_in_bottom_row, path_from_hex, path_to_hex,
+    solution_len_at_least, solvable_with, AutoPlay, Beam, Config, Game, Generator, Graph, Limits,
+    Move, Path, Portfolio, SolutionFound, Solver, TraceEvent, TraceSummary, Tracer, CELL_NUM,
+};
 
-fn print_link(deal: u64, path: &Path) {
-    let mut buf = String::with_capacity(path.len() * 2);
-    for mv in path {
-        buf.push_str(&spot_to_hex(mv.giver()));
-        buf.push_str(&spot_to_hex(mv.taker()));
-    }
+pub fn print_link(deal: u64, path: &Path) {
+    println!("{}\n", demo_link(deal, &path_to_hex(path)));
+}
 
+/// Prints a solution as a JSON line: the search progress is given unless it's a cached one.
+fn print_json(deal: u64, path: &Path, found: Option<&SolutionFound>) {
+    let hex = path_to_hex(path);
     println!(
-        "https://constf1.github.io/angular/freecell-demo?deal={}&path={}\n",
-        deal, buf
+        "{}",
+        serde_json::json!({
+            "len": path.len(),
+            "link": demo_link(deal, &hex),
+            "path": hex,
+            "cached": found.is_none(),
+            "expanded": found.map(|found| found.expanded),
+            "done": found.map(|found| found.done),
+            "elapsed_ms": found
+                .and_then(|found| found.elapsed)
+                .map(|elapsed| elapsed.as_millis() as u64),
+        })
     );
 }
 
+fn open_cache(file: &str) -> Cache {
+    Cache::open(file).unwrap_or_else(|err| {
+        eprintln!("{}: {}", file, err);
+        std::process::exit(1);
+    })
+}
+
+/// Returns a tracer writing the events to the file, exiting on an error.
+fn trace_to(file: &str) -> Tracer {
+    let open = |file: &str| -> io::Result<BufWriter<std::fs::File>> {
+        Ok(BufWriter::new(std::fs::File::create(file)?))
+    };
+    let mut writer = open(file).unwrap_or_else(|err| {
+        eprintln!("{}: {}", file, err);
+        std::process::exit(1);
+    });
+    let file = file.to_string();
+    Tracer::new(move |event| {
+        let written = serde_json::to_string(event)
+            .map_err(io::Error::from)
+            .and_then(|line| writeln!(writer, "{}", line));
+        if let Err(err) = written {
+            eprintln!("{}: {}", file, err);
+            std::process::exit(1);
+        }
+    })
+}
+
+/// Joins a path prefix and a path relative to the position it leads to.
+fn join_paths(prefix: &[Move], path: &[Move]) -> Path {
+    prefix.iter().chain(path).cloned().collect()
+}
+
+/// Deals the game and makes the prefix moves, exiting on an illegal one.
+fn start_position(deal: u64, cells: usize, prefix: &[Move]) -> Game {
+    let mut start = Game::new();
+    start.set_cell_num(cells);
+    start.deal(&deck::deal(deal));
+    for (i, mv) in prefix.iter().enumerate() {
+        if !start.is_valid_move(mv.giver().index(), mv.taker().index()) {
+            eprintln!("Illegal move #{}: {} -> {}", i + 1, mv.giver(), mv.taker());
+            std::process::exit(1);
+        }
+        start.apply(mv);
+    }
+    start
+}
+
 fn print_path(game: &mut Game, path: &Path) {
     game.rewind();
     // println!("\n{}", game);
@@ -30,169 +103,387 @@ fn print_path(game: &mut Game, path: &Path) {
         println!(
             "{}. {}: {} -> {}",
             i + 1,
-            deck::card_to_string(*game.card_at(giver).expect("Giver should exist")),
-            spot_name(giver),
-            spot_name(taker)
+            game.card_at(giver.index()).expect("Giver should exist"),
+            giver,
+            taker
         );
 
-        game.move_card(giver, taker);
+        game.apply(mv);
         // println!("{}", game);
     }
 }
 
-fn is_unsigned<T: FromStr>(v: String) -> Result<(), String> {
-    match v.parse::<T>() {
-        Err(_) => Err(format!(
-            "should be a non-negative integer value, but got '{}'.",
-            v
-        )),
-        Ok(_) => Ok(()),
+/// Parses the value of the argument, if it's given.
+fn parsed<T: FromStr>(matches: &ArgMatches, name: &str) -> Option<T> {
+    matches.value_of(name).and_then(|v| v.parse().ok())
+}
+
+/// Parses the value of the argument, falling back to the default.
+fn parsed_or<T: FromStr>(matches: &ArgMatches, name: &str, default: DefaultParam<T>) -> T {
+    parsed(matches, name).unwrap_or(default.value)
+}
+
+/// Returns the search limits given by the arguments of the command.
+/// The omitted ones get the library defaults, except for `done_max`.
+fn limits_of(matches: &ArgMatches, done_max: DefaultParam<usize>) -> Limits {
+    let defaults = Limits::default();
+    Limits {
+        path_max: parsed(matches, arg::PATH_MAX).map_or(defaults.path_max, |max: usize| max + 1),
+        // At least one path should be processed.
+        grab_max: parsed(matches, arg::GRAB_MAX)
+            .unwrap_or(defaults.grab_max)
+            .max(1),
+        done_max: parsed_or(matches, arg::DONE_MAX, done_max),
+        any: matches.is_present(arg::ANY),
+        time_max: parsed(matches, arg::TIME_MAX).map(Duration::from_millis),
+    }
+}
+
+/// Returns the starting position given by the deal, cells and path arguments.
+fn start_of(matches: &ArgMatches) -> (u64, Path, Game) {
+    let deal = parsed_or(matches, arg::DEAL, DEAL);
+    let cells = parsed_or(matches, arg::CELLS, CELLS).min(CELL_NUM);
+    let prefix = matches
+        .value_of(arg::FROM)
+        .and_then(path_from_hex)
+        .unwrap_or_default();
+    let start = start_position(deal, cells, &prefix);
+    (deal, prefix, start)
+}
+
+fn play_deal(matches: &ArgMatches) {
+    let limits = Limits {
+        any: true,
+        ..limits_of(matches, DONE_MAX)
+    };
+    if let Err(err) = play::Play::new(parsed_or(matches, arg::DEAL, DEAL), limits).run() {
+        eprintln!("{}", err);
     }
 }
 
-pub struct DefaultParam<T> {
-    value: T,
-    name: &'static str,
+fn serve_requests(matches: &ArgMatches) {
+    let jobs = parsed(matches, arg::JOBS)
+        .or_else(|| std::thread::available_parallelism().ok().map(|n| n.get()))
+        .unwrap_or(1);
+    #[cfg(feature = "http")]
+    if let Some(addr) = matches.value_of(arg::HTTP) {
+        let timeout = parsed_or(matches, arg::TIMEOUT, TIMEOUT);
+        if let Err(err) = http::run(addr, jobs, Duration::from_millis(timeout)) {
+            eprintln!("{}", err);
+        }
+        return;
+    }
+    if let Err(err) = serve::run(jobs) {
+        eprintln!("{}", err);
+    }
 }
 
-macro_rules! define_param {
-    ( $name:ident : $t:ty = $val:expr ) => {
-        pub const $name: DefaultParam<$t> = DefaultParam {
-            value: $val,
-            name: stringify!($val),
+fn generate_deals(matches: &ArgMatches) {
+    let rng = parsed_or(matches, arg::RNG, RNG);
+    let mut generator = if matches.is_present(arg::SAMPLE) {
+        Generator::sampled_seeds(rng, 1 << 32)
+    } else if matches.is_present(arg::PERMUTATIONS) {
+        Generator::permutations(rng)
+    } else {
+        Generator::seeds(parsed_or(matches, arg::START, DEAL))
+    };
+    if let Some(tries) = parsed(matches, arg::TRIES) {
+        generator = generator.tries(tries);
+    }
+
+    let limits = limits_of(matches, FILTER_DONE_MAX);
+    // Cheap checks go first.
+    if matches.is_present(arg::NO_BOTTOM_ACES) {
+        generator = generator.filter(no_aces_in_bottom_row());
+    }
+    if let Some(locks) = parsed(matches, arg::MIN_LOCKS) {
+        generator = generator.filter(locks_at_least(locks));
+    }
+    if let Some(cells) = parsed(matches, arg::SOLVABLE_CELLS) {
+        let limits = Limits {
+            any: true,
+            ..limits.clone()
         };
+        generator = generator.filter(solvable_with(cells, limits));
+    }
+    if let Some(len) = parsed(matches, arg::MIN_LEN) {
+        generator = generator.filter(solution_len_at_least(len, limits));
+    }
+
+    let board = matches.is_present(arg::BOARD);
+    for deal in generator.take(parsed_or(matches, arg::COUNT, COUNT)) {
+        match deal.seed {
+            Some(seed) if board => println!("{} {}", seed, deck::to_string(&deal.cards)),
+            Some(seed) => println!("{}", seed),
+            None => println!("{}", deck::to_string(&deal.cards)),
+        }
+    }
+}
+
+fn manage_cache(matches: &ArgMatches) {
+    let file = matches.value_of(arg::FILE).unwrap_or_default();
+    let mut cache = open_cache(file);
+    let result = if let Some(other) = matches.value_of(arg::IMPORT) {
+        std::fs::File::open(other)
+            .and_then(|other| cache.import(BufReader::new(other)))
+            .map(|count| println!("Improved: {}", count))
+    } else if matches.is_present(arg::EXPORT) {
+        cache.export(io::stdout().lock())
+    } else {
+        for improvement in cache.improvements() {
+            match improvement.from {
+                Some(from) => println!("{}: {} -> {}", improvement.key, from, improvement.to),
+                None => println!("{}: {}", improvement.key, improvement.to),
+            }
+        }
+        Ok(())
     };
+    if let Err(err) = result {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    }
 }
 
-define_param!(DEAL: u64 = 0);
-define_param!(PATH_MAX: usize = 256);
-define_param!(GRAB_MAX: usize = 1000);
-define_param!(DONE_MAX: usize = 10000000);
+fn summarize_trace(matches: &ArgMatches) {
+    let file = matches.value_of(arg::FILE).unwrap_or_default();
+    let summary = std::fs::File::open(file).and_then(|reader| {
+        let mut summary = TraceSummary::new();
+        for (i, line) in BufReader::new(reader).lines().enumerate() {
+            let event: TraceEvent = serde_json::from_str(&line?).map_err(|err| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("line {}: {}", i + 1, err),
+                )
+            })?;
+            summary.add(&event);
+        }
+        Ok(summary)
+    });
+    match summary {
+        Ok(summary) => print!("{}", summary),
+        Err(err) => {
+            eprintln!("{}: {}", file, err);
+            std::process::exit(1);
+        }
+    }
+}
 
-fn main() {
-    let deal = "deal";
-    let path_max = "path-max";
-    let grab_max = "grab-max";
-    let done_max = "done-max";
-    let verbose = "verbose";
-    let any = "any";
-
-    let matches = App::new("FreeCell Solver")
-        .version(crate_version!())
-        .about("Solves FreeCell solitaries for [https://constf1.github.io/angular/freecell-demo]")
-        // Regular App configuration goes here...
-        .arg(
-            Arg::with_name(deal)
-                .help("The deal number to use") // Displayed when showing help info.
-                .index(1) // Set the order in which the user must specify this argument.
-                .required(true) // By default this argument MUST be present.
-                .value_name("NUMBER")
-                .validator(is_unsigned::<u64>), // It should be a non-negative integer value.
-        )
-        .arg(
-            Arg::with_name(path_max)
-                .help("The upper bound of the search range (inclusive)")
-                .short("P")
-                .long("path")
-                .required(false)
-                .takes_value(true)
-                .default_value(PATH_MAX.name)
-                .value_name("NUMBER")
-                .validator(is_unsigned::<usize>),
-        )
-        .arg(
-            Arg::with_name(grab_max)
-                .help("The maximum number of variants to be processed in one iteration")
-                .short("S")
-                .long("scoop")
-                .required(false)
-                .takes_value(true)
-                .default_value(GRAB_MAX.name)
-                .value_name("NUMBER")
-                .validator(is_unsigned::<usize>),
-        )
-        .arg(
-            Arg::with_name(done_max)
-                .help("The maximum number of variants to be processed in total")
-                .short("L")
-                .long("limit")
-                .required(false)
-                .takes_value(true)
-                .default_value(DONE_MAX.name)
-                .value_name("NUMBER")
-                .validator(is_unsigned::<usize>),
-        )
-        .arg(
-            Arg::with_name(verbose)
-                .help("Use debug output")
-                .short("D")
-                .long("debug")
-                .alias("verbose")
-                .required(false),
-        )
-        .arg(
-            Arg::with_name(any)
-                .help("Stop on the first result")
-                .short("A")
-                .long("any")
-                .required(false),
-        )
-        .get_matches();
-
-    let deal = matches
-        .value_of(deal)
-        .and_then(|v| v.parse::<u64>().ok())
-        .unwrap_or(DEAL.value);
-    let path_max = 1 + matches
-        .value_of(path_max)
-        .and_then(|v| v.parse::<usize>().ok())
-        .unwrap_or(PATH_MAX.value);
-    let grab_max = matches
-        .value_of(grab_max)
-        .and_then(|v| v.parse::<usize>().ok())
-        .unwrap_or(GRAB_MAX.value)
-        .max(1); // At least one path should be processed.
-    let done_max = matches
-        .value_of(done_max)
-        .and_then(|v| v.parse::<usize>().ok())
-        .unwrap_or(DONE_MAX.value)
-        .max(1000); // At least one thousand paths should be processed.
-    let verbose = matches.is_present(verbose);
-    let any = matches.is_present(any);
+fn run_bench(matches: &ArgMatches) {
+    let first = parsed_or(matches, arg::START, DEAL);
+    let count = parsed_or(matches, arg::COUNT, COUNT) as u64;
+    let beam = Beam {
+        width: parsed_or(matches, arg::BEAM, BEAM_WIDTH),
+        widen: parsed_or(matches, arg::WIDEN, BEAM_WIDEN),
+        width_max: parsed_or(matches, arg::WIDTH_MAX, BEAM_WIDTH_MAX),
+        ..Beam::default()
+    };
+    bench::run(
+        first..first.saturating_add(count),
+        &limits_of(matches, FILTER_DONE_MAX),
+        &beam,
+    );
+}
 
-    let mut sol = Solver::new();
-    sol.deal(deal);
-    let (mut game, path) = loop {
-        let mut stop = true;
-
-        if let Some(found) = sol.next(path_max, grab_max, verbose) {
-            if found {
-                if let Some(path) = &sol.path() {
-                    println!("Path ({}):", path.len());
-                    print_link(deal, path);
-                }
+fn run_portfolio(matches: &ArgMatches) {
+    let deal = parsed_or(matches, arg::DEAL, DEAL);
+    let portfolio = Portfolio::new(
+        Config::variations(parsed_or(matches, arg::CONFIGS, CONFIGS).max(1)),
+        limits_of(matches, FILTER_DONE_MAX),
+    )
+    .slice(parsed_or(matches, arg::SLICE, SLICE));
+    for (i, config) in portfolio.configs().iter().enumerate() {
+        println!("#{}: {}", i + 1, config);
+    }
+    println!();
+
+    let mut game = Game::new();
+    game.deal(&deck::deal(deal));
+    let win = if matches.is_present(arg::IN_TURNS) {
+        portfolio.run_in_turns(&game)
+    } else {
+        portfolio.run_concurrently(&game)
+    };
+    match win {
+        Some(win) => {
+            println!(
+                "Winner: #{} ({})",
+                win.config + 1,
+                portfolio.configs()[win.config]
+            );
+            println!("Done: {}", win.done);
+            println!("Path ({}):", win.path.len());
+            print_link(deal, &win.path);
+        }
+        None => println!("Solution not found!"),
+    }
+}
+
+fn print_graph(matches: &ArgMatches) {
+    let (_, _, start) = start_of(matches);
+    let solution = if matches.is_present(arg::SOLVE) {
+        let mut sol = Solver::new();
+        sol.set_cell_num(start.cell_num());
+        sol.start_from(&start);
+        let limits = Limits {
+            any: true,
+            ..limits_of(matches, FILTER_DONE_MAX)
+        };
+        let path = sol.solve(&limits).cloned();
+        if path.is_none() {
+            eprintln!("Solution not found!");
+        }
+        path
+    } else {
+        matches.value_of(arg::SOLUTION).and_then(path_from_hex)
+    };
+
+    let graph = Graph::explore(
+        &start,
+        parsed_or(matches, arg::DEPTH, GRAPH_DEPTH),
+        solution.as_deref(),
+    );
+    if matches.is_present(arg::JSON) {
+        println!(
+            "{}",
+            serde_json::to_string(&graph).expect("Graph should be serialized")
+        );
+    } else {
+        print!("{}", graph.to_dot());
+    }
+}
+
+fn print_min_cells(deal: u64, limits: &Limits, verbose: bool) {
+    match min_cells(deal, limits) {
+        Some((cell_num, path)) => {
+            println!("Cells: {}", cell_num);
+            println!("Path ({}):", path.len());
+            print_link(deal, &path);
+
+            if verbose {
+                let mut game = Game::new();
+                game.deal(&deck::deal(deal));
+                println!("Deal #{}", deal);
+                println!("{}\n", game);
+                println!("Solution:");
+                print_path(&mut game, &path);
             }
+        }
+        None => println!("Solution not found!"),
+    }
+}
 
-            if !(any && found) {
-                stop = sol.done().len() > done_max;
-                if stop && verbose {
-                    println!(
-                        "Done: {}, {} still in process, but we're over the limit!\n",
-                        sol.done().len(),
-                        sol.bank().len()
-                    );
-                }
+fn beam_search(deal: u64, prefix: &[Move], game: &mut Game, beam: &Beam, verbose: bool) {
+    match beam.solve(game) {
+        Some((path, width)) => {
+            println!("Path ({}), width {}:", path.len(), width);
+            print_link(deal, &join_paths(prefix, &path));
+            if verbose {
+                println!("Deal #{}", deal);
+                println!("{}\n", game);
+                println!("Solution:");
+                print_path(game, &join_paths(prefix, &path));
             }
+        }
+        None => println!("Solution not found!"),
+    }
+}
+
+fn solve_deal(matches: &ArgMatches) {
+    let mut limits = limits_of(matches, DONE_MAX);
+    // At least one thousand paths should be processed.
+    limits.done_max = limits.done_max.max(1000);
+    let verbose = matches.is_present(arg::VERBOSE);
+    let auto_play: AutoPlay = parsed(matches, arg::AUTO).unwrap_or_default();
+    let (deal, prefix, mut start) = start_of(matches);
+    start.set_auto_play(auto_play);
+
+    if let Some(width) = parsed(matches, arg::BEAM) {
+        let beam = Beam {
+            width,
+            path_max: limits.path_max,
+            ..Beam::default()
         };
+        beam_search(deal, &prefix, &mut start, &beam, verbose);
+        return;
+    }
+    if matches.is_present(arg::MIN_CELLS) {
+        print_min_cells(deal, &limits, verbose);
+        return;
+    }
 
-        if stop {
-            break sol.into_solution();
+    let key = Key::deal(deal, &start);
+    let mut cache = matches.value_of(arg::CACHE).map(open_cache);
+    // A cached solution helps only if it goes through the starting position.
+    let cached = cache
+        .as_ref()
+        .and_then(|cache| cache.get(&key))
+        .filter(|path| path.starts_with(&prefix))
+        .map(|path| path[prefix.len()..].to_vec());
+    let ndjson = matches.is_present(arg::NDJSON);
+    if let Some(path) = &cached {
+        if ndjson {
+            print_json(deal, &join_paths(&prefix, path), None);
+        } else {
+            println!("Cached path ({}):", path.len());
+            print_link(deal, &join_paths(&prefix, path));
         }
-    };
+    }
+    // Only shorter paths are searched for.
+    if let Some(path) = &cached {
+        limits.path_max = limits.path_max.min(path.len());
+    }
+
+    let mut sol = Solver::new();
+    sol.set_cell_num(start.cell_num());
+    sol.set_auto_play(auto_play);
+    if let Some(file) = matches.value_of(arg::TRACE) {
+        sol.set_tracer(Some(trace_to(file)));
+    }
+    if let Some(order) = parsed(matches, arg::ORDER) {
+        sol.set_move_order(order);
+    }
+    let stats = matches.is_present(arg::STATS);
+    sol.set_timed(stats || ndjson);
+    sol.start_from(&start);
+    // With a cached path, any solution is already there.
+    if !(limits.any && cached.is_some()) {
+        sol.solve_anytime(&limits, |found, path| {
+            if ndjson {
+                print_json(deal, &join_paths(&prefix, path), Some(found));
+            } else {
+                println!("Path ({}):", path.len());
+                print_link(deal, &join_paths(&prefix, path));
+            }
+            true
+        });
+        if verbose && sol.done().len() > limits.done_max {
+            println!(
+                "Done: {}, {} still in process, but we're over the limit!\n",
+                sol.done().len(),
+                sol.bank().len()
+            );
+        }
+    }
+    if stats {
+        println!("{}", sol.stats());
+    }
+    let (mut game, path) = sol.into_solution();
+
+    if let (Some(cache), Some(path)) = (&mut cache, &path) {
+        match cache.insert(key, join_paths(&prefix, path)) {
+            Ok(true) if !ndjson => println!("Cache updated.\n"),
+            Ok(_) => {}
+            Err(err) => eprintln!("{}", err),
+        }
+    }
+    let path = path.or(cached);
 
     if verbose {
         game.rewind();
         println!("Deal #{}", deal);
+        if !prefix.is_empty() {
+            println!("From: {}", path_to_hex(&prefix));
+        }
         println!("{}\n", game);
 
         if let Some(path) = path {
@@ -203,3 +494,18 @@ fn main() {
         }
     }
 }
+
+fn main() {
+    let matches = app().get_matches();
+    match matches.subcommand() {
+        (arg::PLAY, Some(matches)) => play_deal(matches),
+        (arg::SERVE, Some(matches)) => serve_requests(matches),
+        (arg::GENERATE, Some(matches)) => generate_deals(matches),
+        (arg::CACHE, Some(matches)) => manage_cache(matches),
+        (arg::TRACE, Some(matches)) => summarize_trace(matches),
+        (arg::BENCH, Some(matches)) => run_bench(matches),
+        (arg::PORTFOLIO, Some(matches)) => run_portfolio(matches),
+        (arg::GRAPH, Some(matches)) => print_graph(matches),
+        _ => solve_deal(&matches),
+    }
+}
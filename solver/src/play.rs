@@ -0,0 +1,293 @@
+//! Interactive terminal play mode.
+
+use std::io::{self, BufRead, Write};
+
+use freecell_solver::deck;
+use freecell_solver::freecell::{
+    hint_with, status, Game, Hint, History, Limits, Move, Spot, Status,
+};
+
+const HELP: &str = "\
+Moves:
+    <from><to>          standard notation: 1-8 for piles, a-d for cells, h for home (e.g. 3a, a5, 7h)
+    <spot> <spot>       spot names (e.g. pile 3 cell 1, cell 1 -> base 2)
+Commands:
+    u, undo             take back the last move
+    r, redo             replay the last taken back move
+    v, variations       list the moves played from this position before
+    mark <name>         name this position
+    go <name>           go to the named position
+    marks               list the named positions
+    h, hint             show the next move of a solution or the heuristic best one
+    m, moves            list available moves
+    b, board            show the board
+    l, link             show the demo link of the current game
+    ?, help             show this help
+    q, quit             leave the game";
+
+/// The maximum number of positions to be explored to detect a lost game.
+const STATUS_DONE_MAX: usize = 10000;
+
+/// Interactive game session.
+pub struct Play {
+    deal: u64,
+    history: History,
+    limits: Limits,
+}
+
+/// Returns the standard notation of a spot: 1-8 for piles, a-d for cells and h for bases.
+pub fn spot_to_notation(spot: Spot) -> char {
+    match spot {
+        Spot::Cascade(n) => (b'1' + n as u8) as char,
+        Spot::Cell(n) => (b'a' + n as u8) as char,
+        Spot::Foundation(_) => 'h',
+    }
+}
+
+fn notation_to_spot(c: char) -> Option<Spot> {
+    match c {
+        '1'..='8' => Some(Spot::Cascade(c as usize - '1' as usize)),
+        'a'..='d' => Some(Spot::Cell(c as usize - 'a' as usize)),
+        'h' => Some(Spot::Foundation(0)),
+        _ => None,
+    }
+}
+
+fn name_to_spot(name: &str, number: &str) -> Option<Spot> {
+    let n = number.parse::<usize>().ok()?.checked_sub(1)?;
+    let spot = match name {
+        "pile" => Spot::Cascade(n),
+        "cell" => Spot::Cell(n),
+        "base" => Spot::Foundation(n),
+        _ => return None,
+    };
+    Some(spot).filter(|spot| spot.is_valid())
+}
+
+/// Parses a move either in standard notation ("3a") or with spot names ("pile 3 -> cell 1").
+/// The home taker ('h') is resolved to the base the card belongs to.
+pub fn parse_move(game: &Game, input: &str) -> Option<Move> {
+    let words: Vec<&str> = input
+        .split_whitespace()
+        .filter(|&word| word != "->")
+        .collect();
+    let (giver, mut taker) = match words.as_slice() {
+        [word] => {
+            let mut chars = word.chars();
+            match (chars.next(), chars.next(), chars.next()) {
+                (Some(a), Some(b), None) => (notation_to_spot(a)?, notation_to_spot(b)?),
+                _ => return None,
+            }
+        }
+        [giver_name, giver_number, taker_name, taker_number] => (
+            name_to_spot(giver_name, giver_number)?,
+            name_to_spot(taker_name, taker_number)?,
+        ),
+        _ => return None,
+    };
+    if let (Spot::Foundation(_), 1) = (taker, words.len()) {
+        taker = Spot::from_index(game.get_base(game.card_at(giver.index())?)?)?;
+    }
+    Move::new(giver, taker)
+}
+
+/// Formats a move, e.g. "3a (7♠: pile 3 -> cell 1)".
+pub fn move_to_string(game: &Game, mv: &Move) -> String {
+    let card = game
+        .card_at(mv.giver().index())
+        .map(|card| card.to_string())
+        .unwrap_or_default();
+    format!(
+        "{}{} ({}: {} -> {})",
+        spot_to_notation(mv.giver()),
+        spot_to_notation(mv.taker()),
+        card,
+        mv.giver(),
+        mv.taker()
+    )
+}
+
+impl Play {
+    pub fn new(deal: u64, limits: Limits) -> Self {
+        let mut game = Game::new();
+        game.deal(&deck::deal(deal));
+        game.move_cards_auto();
+        Self {
+            deal,
+            history: History::new(game),
+            limits,
+        }
+    }
+
+    pub fn game(&self) -> &Game {
+        self.history.game()
+    }
+
+    /// Makes a player move followed by the automatic moves to bases.
+    pub fn make_move(&mut self, mv: &Move) -> bool {
+        self.history.play_auto(mv)
+    }
+
+    pub fn undo(&mut self) -> bool {
+        self.history.undo()
+    }
+
+    pub fn redo(&mut self) -> bool {
+        self.history.redo()
+    }
+
+    /// Returns the next move of a solution found from the current position
+    /// or the heuristic best one.
+    pub fn hint(&self) -> Option<Hint> {
+        hint_with(self.game(), &self.limits)
+    }
+
+    /// Runs the read-eval-print loop until the game is over or the input ends.
+    pub fn run(&mut self) -> io::Result<()> {
+        let stdin = io::stdin();
+        let mut stdout = io::stdout();
+
+        println!("Deal #{}. Type '?' for help.", self.deal);
+        println!("{}\n", self.game());
+        print!("> ");
+        stdout.flush()?;
+
+        for line in stdin.lock().lines() {
+            let line = line?;
+            let input = line.trim();
+            // The proof search runs only when the position changes.
+            let mut moved = false;
+            match input {
+                "" => {}
+                "q" | "quit" => break,
+                "?" | "help" => println!("{}", HELP),
+                "b" | "board" => println!("{}", self.game()),
+                "l" | "link" => crate::print_link(self.deal, self.game().path()),
+                "u" | "undo" => match self.undo() {
+                    true => {
+                        moved = true;
+                        println!("{}", self.game())
+                    }
+                    false => println!("Nothing to undo."),
+                },
+                "r" | "redo" => match self.redo() {
+                    true => {
+                        moved = true;
+                        println!("{}", self.game())
+                    }
+                    false => println!("Nothing to redo."),
+                },
+                "h" | "hint" => match self.hint() {
+                    Some(hint) => println!(
+                        "Hint: {} ({})",
+                        move_to_string(self.game(), &hint.mv),
+                        if hint.winning {
+                            "winning"
+                        } else {
+                            "heuristic only"
+                        }
+                    ),
+                    None => println!("No moves."),
+                },
+                "v" | "variations" => {
+                    let game = self.game();
+                    for &id in self.history.variations() {
+                        let moves = self.history.moves(id);
+                        match moves.len() {
+                            1 => println!("{}", move_to_string(game, &moves[0])),
+                            n => {
+                                println!("{} +{} to bases", move_to_string(game, &moves[0]), n - 1)
+                            }
+                        }
+                    }
+                }
+                "marks" => {
+                    for (name, id) in self.history.bookmarks() {
+                        let here = if id == self.history.current() {
+                            " (here)"
+                        } else {
+                            ""
+                        };
+                        println!("{}{}", name, here);
+                    }
+                }
+                _ if input.starts_with("mark ") => {
+                    self.history.bookmark(input["mark ".len()..].trim());
+                    println!("Marked.");
+                }
+                _ if input.starts_with("go ") => {
+                    match self.history.goto_bookmark(input["go ".len()..].trim()) {
+                        true => {
+                            moved = true;
+                            println!("{}", self.game())
+                        }
+                        false => println!("No such mark."),
+                    }
+                }
+                "m" | "moves" => {
+                    for mv in self.game().get_all_moves() {
+                        println!("{}", move_to_string(self.game(), &mv));
+                    }
+                }
+                _ => match parse_move(self.game(), input) {
+                    Some(mv) => {
+                        if self.make_move(&mv) {
+                            moved = true;
+                            println!("{}", self.game());
+                        } else {
+                            println!("Illegal move: {}", input);
+                        }
+                    }
+                    None => println!("Unknown command: '{}'. Type '?' for help.", input),
+                },
+            }
+
+            if self.game().is_done() {
+                println!("Solved in {} moves!", self.game().path().len());
+                crate::print_link(self.deal, self.game().path());
+                break;
+            }
+            if moved && status(self.game(), STATUS_DONE_MAX) == Status::Lost {
+                println!("No more winning moves. Type 'u' to undo.");
+            }
+
+            print!("> ");
+            stdout.flush()?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn notation() {
+        let mut play = Play::new(173205951, Limits::default());
+        let game = play.game();
+
+        let mv = parse_move(game, "8a").expect("Should be parsed!");
+        assert_eq!((Spot::Cascade(7), Spot::Cell(0)), (mv.giver(), mv.taker()));
+
+        let mv = parse_move(game, "pile 8 -> cell 1").expect("Should be parsed!");
+        assert_eq!((Spot::Cascade(7), Spot::Cell(0)), (mv.giver(), mv.taker()));
+
+        assert!(parse_move(game, "9a").is_none());
+        assert!(parse_move(game, "pile 9 cell 1").is_none());
+        assert!(parse_move(game, "8ab").is_none());
+
+        let len = play.game().path().len();
+        assert!(play.make_move(&mv));
+        assert!(play.game().path().len() > len);
+        let mv = Move::new(Spot::Cascade(0), Spot::Cell(0)).expect("Should be valid!");
+        assert!(!play.make_move(&mv));
+
+        assert!(play.undo());
+        assert_eq!(len, play.game().path().len());
+        assert!(!play.undo());
+        assert!(play.redo());
+        assert!(!play.redo());
+        assert!(play.game().path().len() > len);
+    }
+}
@@ -0,0 +1,445 @@
+//! Line protocol server mode.
+//!
+//! Reads newline-delimited JSON requests on stdin and writes one JSON response line per request on stdout.
+//! Requests are processed concurrently, so responses may come out of order: they carry the request id.
+//!
+//! A request names a method and its parameters, e.g.
+//! `{"id": 1, "method": "solve", "params": {"deal": 173205951, "limits": {"done_max": 100000, "any": true}}}`.
+//! The position is given by either a `deal` number or a `board` text (see [`parse_board`]),
+//! optionally followed by a hex `path`, and played with `cells` free cells (4 by default).
+//! `limits` are the [`Limits`] of the search, all fields optional, with `time_max` in milliseconds.
+//! Omitted fields get the library defaults, except for `done_max`, `time_max` and `any`:
+//! a request gets at most 1000000 positions and 10 seconds by default, and `hint` and `rate`
+//! stop at the first solution found (`any` is true), since they don't report the solution itself.
+//!
+//! Methods:
+//! - `solve`: `{"solved": bool, "path": HEX, "len": N, "link": URL, "done": N}`,
+//!   where the path includes the given one and the demo link is given for deals only.
+//!   With `"anytime": true`, each shorter solution is reported as soon as it's found, before the result:
+//!   `{"id": ID, "progress": {"path": HEX, "len": N, "link": URL, "done": N, "expanded": N, "elapsed_ms": N}}`.
+//! - `verify`: `{"valid": bool, "solved": bool, "len": N}` or `{"valid": false, "reason": TEXT}`.
+//! - `hint`: `{"move": HEX, "winning": bool}` or `null` if there are no moves.
+//! - `rate`: `{"cells": N, "len": N, "locks": N, "estimate": N}`, where `cells` is the smallest
+//!   number of cells the position can be solved with, and `len` is the length of that solution
+//!   (both `null` if no solution is found).
+//!
+//! Responses are `{"id": ID, "result": RESULT}` or `{"id": ID, "error": TEXT}`.
+
+use std::io::{self, BufRead, Write};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use serde::{Deserialize, Deserializer};
+use serde_json::{json, Value};
+
+use freecell_solver::deck;
+use freecell_solver::freecell::{
+    demo_link, hint_with, min_cells_from, parse_board, path_from_hex, path_to_hex, Game, Limits,
+    Path, Solver, CELL_NUM,
+};
+
+/// The default maximum number of positions processed for a request.
+const DONE_MAX: usize = 1_000_000;
+/// The default maximum search time of a request.
+const TIME_MAX: Duration = Duration::from_secs(10);
+
+/// Returns the default limits of a request, so that no request keeps a worker busy for long.
+fn request_limits(any: bool) -> Limits {
+    Limits {
+        done_max: DONE_MAX,
+        time_max: Some(TIME_MAX),
+        any,
+        ..Limits::default()
+    }
+}
+
+/// The limits given in a request, the omitted ones get the defaults of the method.
+#[derive(Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct GivenLimits {
+    pub path_max: Option<usize>,
+    pub grab_max: Option<usize>,
+    pub done_max: Option<usize>,
+    pub any: Option<bool>,
+    #[serde(default, deserialize_with = "deserialize_millis")]
+    pub time_max: Option<Duration>,
+}
+
+fn deserialize_millis<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Duration>, D::Error> {
+    Ok(Option::<u64>::deserialize(deserializer)?.map(Duration::from_millis))
+}
+
+impl GivenLimits {
+    /// Returns the given limits, with the omitted ones taken from the defaults.
+    fn or(&self, defaults: Limits) -> Limits {
+        Limits {
+            path_max: self.path_max.unwrap_or(defaults.path_max),
+            grab_max: self.grab_max.unwrap_or(defaults.grab_max),
+            done_max: self.done_max.unwrap_or(defaults.done_max),
+            any: self.any.unwrap_or(defaults.any),
+            time_max: self.time_max.or(defaults.time_max),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Request {
+    #[serde(default)]
+    id: Value,
+    #[serde(flatten)]
+    method: Method,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "method", content = "params", rename_all = "lowercase")]
+pub(crate) enum Method {
+    Solve(Params),
+    Verify(Params),
+    Hint(Params),
+    Rate(Params),
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Params {
+    pub deal: Option<u64>,
+    pub board: Option<String>,
+    #[serde(default)]
+    pub path: String,
+    #[serde(default = "default_cells")]
+    pub cells: usize,
+    #[serde(default)]
+    pub limits: GivenLimits,
+    /// Reports each shorter solution found by `solve`.
+    #[serde(default)]
+    pub anytime: bool,
+}
+
+fn default_cells() -> usize {
+    CELL_NUM
+}
+
+impl Params {
+    /// Returns the starting position, before the path.
+    fn start(&self) -> Result<Game, String> {
+        let cards = match (self.deal, &self.board) {
+            (Some(deal), None) => deck::deal(deal).to_vec(),
+            (None, Some(board)) => parse_board(board).ok_or("malformed board")?,
+            _ => return Err("either deal or board expected".to_string()),
+        };
+        if self.cells > CELL_NUM {
+            return Err(format!("at most {} cells expected", CELL_NUM));
+        }
+        let mut game = Game::new();
+        game.set_cell_num(self.cells);
+        game.deal(&cards);
+        Ok(game)
+    }
+
+    /// Plays the path on the game.
+    fn replay(&self, game: &mut Game) -> Result<(), String> {
+        let path = path_from_hex(&self.path).ok_or("malformed path")?;
+        for (i, mv) in path.iter().enumerate() {
+            if !game.is_valid_move(mv.giver().index(), mv.taker().index()) {
+                return Err(format!(
+                    "illegal move #{}: {} -> {}",
+                    i + 1,
+                    mv.giver(),
+                    mv.taker()
+                ));
+            }
+            game.apply(mv);
+        }
+        Ok(())
+    }
+
+    /// Returns the position reached by the path.
+    fn position(&self) -> Result<Game, String> {
+        let mut game = self.start()?;
+        self.replay(&mut game)?;
+        Ok(game)
+    }
+}
+
+fn solve(params: &Params, progress: &mut dyn FnMut(Value)) -> Result<Value, String> {
+    let game = params.position()?;
+    let mut solver = Solver::new();
+    solver.set_cell_num(params.cells);
+    solver.set_timed(params.anytime);
+    solver.start_from(&game);
+    let full_path = |path: &Path| format!("{}{}", params.path, path_to_hex(path));
+    let link = |path: &String| params.deal.map(|deal| demo_link(deal, path));
+    let path = solver
+        .solve_anytime(&params.limits.or(request_limits(false)), |found, path| {
+            if params.anytime {
+                let path = full_path(path);
+                progress(json!({
+                    "len": path.len() / 2,
+                    "link": link(&path),
+                    "path": path,
+                    "done": found.done,
+                    "expanded": found.expanded,
+                    "elapsed_ms": found.elapsed.map(|elapsed| elapsed.as_millis() as u64),
+                }));
+            }
+            true
+        })
+        .map(full_path);
+    Ok(json!({
+        "solved": path.is_some(),
+        "len": path.as_ref().map(|path| path.len() / 2),
+        "link": path.as_ref().and_then(link),
+        "path": path,
+        "done": solver.done().len(),
+    }))
+}
+
+fn verify(params: &Params) -> Result<Value, String> {
+    let mut game = params.start()?;
+    Ok(match params.replay(&mut game) {
+        Ok(()) => json!({
+            "valid": true,
+            "solved": game.is_done(),
+            "len": game.path().len(),
+        }),
+        Err(reason) => json!({
+            "valid": false,
+            "reason": reason,
+        }),
+    })
+}
+
+fn hint(params: &Params) -> Result<Value, String> {
+    let game = params.position()?;
+    Ok(
+        match hint_with(&game, &params.limits.or(request_limits(true))) {
+            Some(hint) => json!({
+                "move": path_to_hex(&vec![hint.mv]),
+                "winning": hint.winning,
+            }),
+            None => Value::Null,
+        },
+    )
+}
+
+fn rate(params: &Params) -> Result<Value, String> {
+    let game = params.position()?;
+    let found = min_cells_from(&game, &params.limits.or(request_limits(true)));
+    Ok(json!({
+        "cells": found.as_ref().map(|(cells, _)| cells),
+        "len": found.as_ref().map(|(_, path)| game.path().len() + path.len()),
+        "locks": game.count_locks(),
+        "estimate": game.estimate_path_len(),
+    }))
+}
+
+/// Runs the method and returns its result. Intermediate results are passed to `progress`.
+pub(crate) fn call(method: &Method, progress: &mut dyn FnMut(Value)) -> Result<Value, String> {
+    match method {
+        Method::Solve(params) => solve(params, progress),
+        Method::Verify(params) => verify(params),
+        Method::Hint(params) => hint(params),
+        Method::Rate(params) => rate(params),
+    }
+}
+
+/// Handles a request line and returns the response line. Progress lines are passed to `progress`.
+pub fn respond_with(line: &str, progress: &mut dyn FnMut(String)) -> String {
+    let response = match serde_json::from_str::<Request>(line) {
+        Ok(Request { id, method }) => match call(&method, &mut |value| {
+            progress(json!({ "id": id, "progress": value }).to_string())
+        }) {
+            Ok(result) => json!({ "id": id, "result": result }),
+            Err(error) => json!({ "id": id, "error": error }),
+        },
+        Err(error) => {
+            // Try to keep the id of a well-formed request with bad parameters.
+            let id = serde_json::from_str::<Value>(line)
+                .ok()
+                .and_then(|value| value.get("id").cloned())
+                .unwrap_or(Value::Null);
+            json!({ "id": id, "error": error.to_string() })
+        }
+    };
+    response.to_string()
+}
+
+/// Serves requests from stdin with the given number of worker threads until the input ends.
+pub fn run(jobs: usize) -> io::Result<()> {
+    let (sender, receiver) = mpsc::channel::<String>();
+    let receiver = Arc::new(Mutex::new(receiver));
+    let stdout = Arc::new(Mutex::new(io::stdout()));
+
+    let workers: Vec<_> = (0..jobs.max(1))
+        .map(|_| {
+            let receiver = Arc::clone(&receiver);
+            let stdout = Arc::clone(&stdout);
+            thread::spawn(move || loop {
+                let line = match receiver.lock().expect("Should be locked!").recv() {
+                    Ok(line) => line,
+                    Err(_) => break,
+                };
+                let write = |line: &str| {
+                    let mut stdout = stdout.lock().expect("Should be locked!");
+                    writeln!(stdout, "{}", line).and_then(|_| stdout.flush())
+                };
+                let response = respond_with(&line, &mut |progress| {
+                    let _ = write(&progress);
+                });
+                if write(&response).is_err() {
+                    break;
+                }
+            })
+        })
+        .collect();
+
+    for line in io::stdin().lock().lines() {
+        let line = line?;
+        if !line.trim().is_empty() && sender.send(line).is_err() {
+            break;
+        }
+    }
+    drop(sender);
+
+    for worker in workers {
+        let _ = worker.join();
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn respond(line: &str) -> String {
+        respond_with(line, &mut |_| {})
+    }
+
+    fn result(line: &str) -> Value {
+        let response: Value = serde_json::from_str(&respond(line)).expect("Should be parsed!");
+        assert_eq!(None, response.get("error"), "{}", line);
+        response["result"].clone()
+    }
+
+    fn error(line: &str) -> Value {
+        let response: Value = serde_json::from_str(&respond(line)).expect("Should be parsed!");
+        assert_eq!(None, response.get("result"), "{}", line);
+        response["error"].clone()
+    }
+
+    #[test]
+    fn requests() {
+        let solved = result(
+            r#"{"id": 1, "method": "solve", "params": {"deal": 173205951, "path": "48", "limits": {"any": true, "done_max": 100000}}}"#,
+        );
+        assert_eq!(json!(true), solved["solved"]);
+        let path = solved["path"].as_str().expect("Should be a path!");
+        assert!(path.starts_with("48"));
+
+        let verified = result(&format!(
+            r#"{{"id": 2, "method": "verify", "params": {{"deal": 173205951, "path": "{}"}}}}"#,
+            path
+        ));
+        assert_eq!(
+            json!({"valid": true, "solved": true, "len": path.len() / 2}),
+            verified
+        );
+
+        let verified =
+            result(r#"{"method": "verify", "params": {"deal": 173205951, "path": "c7"}}"#);
+        assert_eq!(json!(false), verified["valid"]);
+
+        let hint = result(
+            r#"{"method": "hint", "params": {"deal": 173205951, "limits": {"any": true, "done_max": 100000}}}"#,
+        );
+        assert_eq!(json!(true), hint["winning"]);
+
+        let board = deck::to_string(&deck::deal(1));
+        let rated = result(&format!(
+            r#"{{"method": "rate", "params": {{"board": "{}", "limits": {{"any": true}}}}}}"#,
+            board
+        ));
+        assert_eq!(json!(2), rated["cells"]);
+    }
+
+    #[test]
+    fn anytime() {
+        let mut progress = Vec::new();
+        let response = respond_with(
+            r#"{"id": 7, "method": "solve", "params": {"deal": 1, "anytime": true, "limits": {"done_max": 100000}}}"#,
+            &mut |line| progress.push(line),
+        );
+        let response: Value = serde_json::from_str(&response).expect("Should be parsed!");
+        let progress: Vec<Value> = progress
+            .iter()
+            .map(|line| serde_json::from_str(line).expect("Should be parsed!"))
+            .collect();
+
+        assert!(!progress.is_empty());
+        assert!(progress.iter().all(|line| line["id"] == json!(7)));
+        let lens: Vec<u64> = progress
+            .iter()
+            .map(|line| {
+                line["progress"]["len"]
+                    .as_u64()
+                    .expect("Should be a length!")
+            })
+            .collect();
+        assert!(lens.windows(2).all(|pair| pair[0] > pair[1]));
+        assert_eq!(
+            progress[progress.len() - 1]["progress"]["path"],
+            response["result"]["path"]
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let response: Value = serde_json::from_str(&respond(
+            r#"{"id": "a", "method": "solve", "params": {"deal": 1, "board": "AS"}}"#,
+        ))
+        .expect("Should be parsed!");
+        assert_eq!(json!("a"), response["id"]);
+        assert!(response["error"].is_string());
+
+        error(r#"{"id": 3, "method": "fly", "params": {}}"#);
+        error(r#"{"id": 3, "method": "solve", "params": {"deal": 1, "cells": 5}}"#);
+        error(r#"{"id": 3, "method": "solve", "params": {"deal": 1, "path": "4"}}"#);
+        error(r#"{"id": 3, "method": "hint", "params": {"deal": 1, "path": "c7"}}"#);
+        error(r#"{"id": 3, "method": "solve", "params": {"deal": 1, "speed": 5}}"#);
+        error("not json");
+    }
+
+    #[test]
+    fn limits() {
+        let params = |line: &str| match serde_json::from_str::<Request>(line) {
+            Ok(Request {
+                method: Method::Solve(params),
+                ..
+            }) => params,
+            _ => panic!("Should be a solve request!"),
+        };
+        let omitted = params(r#"{"method": "solve", "params": {"deal": 1}}"#)
+            .limits
+            .or(request_limits(false));
+        assert_eq!(DONE_MAX, omitted.done_max);
+        assert_eq!(Some(TIME_MAX), omitted.time_max);
+        assert!(!omitted.any);
+
+        let given = params(
+            r#"{"method": "solve", "params": {"deal": 1, "limits": {"any": true, "time_max": 1500}}}"#,
+        )
+        .limits
+        .or(request_limits(false));
+        assert!(given.any);
+        assert_eq!(DONE_MAX, given.done_max);
+        assert_eq!(Some(Duration::from_millis(1500)), given.time_max);
+        assert_eq!(Limits::default().path_max, given.path_max);
+
+        error(r#"{"method": "solve", "params": {"deal": 1, "limits": {"done_mx": 5}}}"#);
+        error(r#"{"method": "solve", "params": {"deal": 1, "limits": {"time_max": {"secs": 1}}}}"#);
+    }
+}
@@ -0,0 +1,382 @@
+//! # Solution cache
+//! Keeps the best known solution paths in an append-only text file.
+//!
+//! Each line holds an entry: the deal scheme, the deal number or board hash, the rule variant,
+//! the cell count and the hex path, separated by spaces, e.g. `demo 173205951 freecell 4 4871317c...`.
+//! The variant is read from the game, see [`variant`].
+//! An entry is appended only when it improves on the known one, so the file keeps the history
+//! of improvements and the last entry of a key is its best one.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path as FilePath, PathBuf};
+
+use crate::deck::{self, Card};
+use crate::freecell::{path_from_hex, path_to_hex, AutoPlay, Game, Path};
+
+/// The scheme of the numbered deals of the demo, see [`deck::deal`].
+pub const DEAL_SCHEME: &str = "demo";
+/// The scheme of boards given by their cards, identified by a hash.
+pub const BOARD_SCHEME: &str = "board";
+/// The standard FreeCell rules.
+pub const VARIANT: &str = "freecell";
+
+/// Returns the rule variant of the game: [`VARIANT`], followed by the auto-play policy
+/// unless it's the default one, e.g. `freecell:horne`.
+pub fn variant(game: &Game) -> String {
+    if game.auto_play() == AutoPlay::default() {
+        VARIANT.to_string()
+    } else {
+        format!("{}:{}", VARIANT, game.auto_play())
+    }
+}
+
+/// Identifies a game: what is dealt, under which rules and with how many cells.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Key {
+    pub scheme: String,
+    pub id: String,
+    pub variant: String,
+    pub cells: usize,
+}
+
+impl Key {
+    /// The key of a numbered deal played by the rules of the game.
+    pub fn deal(seed: u64, game: &Game) -> Self {
+        Self {
+            scheme: DEAL_SCHEME.to_string(),
+            id: seed.to_string(),
+            variant: variant(game),
+            cells: game.cell_num(),
+        }
+    }
+
+    /// The key of a board given by its cards in the deal order, played by the rules of the game.
+    /// The id is the 64-bit FNV-1a hash of the cards.
+    pub fn board(cards: &[Card], game: &Game) -> Self {
+        let hash = cards.iter().fold(0xcbf29ce484222325_u64, |hash, &card| {
+            (hash ^ u8::from(card) as u64).wrapping_mul(0x100000001b3)
+        });
+        Self {
+            scheme: BOARD_SCHEME.to_string(),
+            id: format!("{:016x}", hash),
+            variant: variant(game),
+            cells: game.cell_num(),
+        }
+    }
+
+    /// Returns the cards of the deal, if the key is of a numbered deal.
+    pub fn cards(&self) -> Option<[Card; deck::CARD_NUM]> {
+        if self.scheme == DEAL_SCHEME {
+            self.id.parse().ok().map(deck::deal)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the dealt game with the rules of the key, if the key is of a numbered deal and a known variant.
+    pub fn game(&self) -> Option<Game> {
+        let cards = self.cards()?;
+        let auto_play = match self.variant.strip_prefix(VARIANT)? {
+            "" => AutoPlay::default(),
+            policy => policy.strip_prefix(':')?.parse().ok()?,
+        };
+        let mut game = Game::new();
+        game.set_cell_num(self.cells);
+        game.set_auto_play(auto_play);
+        game.deal(&cards);
+        Some(game)
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} {}",
+            self.scheme, self.id, self.variant, self.cells
+        )
+    }
+}
+
+/// Formats a cache line.
+pub fn format_entry(key: &Key, path: &Path) -> String {
+    format!("{} {}", key, path_to_hex(path))
+}
+
+/// Parses a cache line formatted by [`format_entry`].
+pub fn parse_entry(line: &str) -> Option<(Key, Path)> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    match fields.as_slice() {
+        [scheme, id, variant, cells, hex] => Some((
+            Key {
+                scheme: scheme.to_string(),
+                id: id.to_string(),
+                variant: variant.to_string(),
+                cells: cells.parse().ok()?,
+            },
+            path_from_hex(hex)?,
+        )),
+        _ => None,
+    }
+}
+
+/// Reads the entries of a cache file, in order. Blank lines and lines starting with '#' are skipped.
+pub fn read_entries(reader: impl BufRead) -> io::Result<Vec<(Key, Path)>> {
+    let mut entries = Vec::new();
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        entries.push(parse_entry(line).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("malformed cache entry at line {}: '{}'", i + 1, line),
+            )
+        })?);
+    }
+    Ok(entries)
+}
+
+/// Returns [`true`] if the path solves the game of the key.
+/// Only numbered deals can be checked, other paths are trusted.
+pub fn is_solution(key: &Key, path: &Path) -> bool {
+    let mut game = match key.game() {
+        Some(game) => game,
+        None => return key.scheme != DEAL_SCHEME,
+    };
+    for mv in path {
+        if !game.is_valid_move(mv.giver().index(), mv.taker().index()) {
+            return false;
+        }
+        game.apply(mv);
+    }
+    game.is_done()
+}
+
+/// A shorter path found for a key.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Improvement {
+    pub key: Key,
+    /// The length of the previously known path, if any.
+    pub from: Option<usize>,
+    pub to: usize,
+}
+
+/// The best known paths, backed by a file.
+pub struct Cache {
+    file: PathBuf,
+    best: BTreeMap<Key, Path>,
+    improvements: Vec<Improvement>,
+}
+
+impl Cache {
+    /// Opens the cache file, which is created on the first insertion if it doesn't exist.
+    pub fn open(file: impl AsRef<FilePath>) -> io::Result<Self> {
+        let mut cache = Self {
+            file: file.as_ref().to_path_buf(),
+            best: BTreeMap::new(),
+            improvements: Vec::new(),
+        };
+        match File::open(&cache.file) {
+            Ok(file) => {
+                for (key, path) in read_entries(BufReader::new(file))? {
+                    cache.update(key, path);
+                }
+            }
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+            Err(err) => return Err(err),
+        }
+        Ok(cache)
+    }
+
+    /// Returns [`true`] if the path is shorter than the known one.
+    fn improves(&self, key: &Key, path: &Path) -> bool {
+        self.best
+            .get(key)
+            .is_none_or(|known| path.len() < known.len())
+    }
+
+    /// Keeps the path if it's shorter than the known one. Returns [`true`] if it is.
+    fn update(&mut self, key: Key, path: Path) -> bool {
+        if !self.improves(&key, &path) {
+            return false;
+        }
+        let from = self.best.get(&key).map(Vec::len);
+        self.improvements.push(Improvement {
+            key: key.clone(),
+            from,
+            to: path.len(),
+        });
+        self.best.insert(key, path);
+        true
+    }
+
+    /// Returns the best known path.
+    pub fn get(&self, key: &Key) -> Option<&Path> {
+        self.best.get(key)
+    }
+
+    /// Records the path if it's shorter than the known one. Returns [`true`] if it is.
+    /// The path is kept only once it's written to the file.
+    pub fn insert(&mut self, key: Key, path: Path) -> io::Result<bool> {
+        if !self.improves(&key, &path) {
+            return Ok(false);
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.file)?;
+        writeln!(file, "{}", format_entry(&key, &path))?;
+        Ok(self.update(key, path))
+    }
+
+    /// Records the entries read from another cache file, skipping the ones which don't solve their games.
+    /// Returns the number of improved paths.
+    pub fn import(&mut self, reader: impl BufRead) -> io::Result<usize> {
+        let mut count = 0;
+        for (key, path) in read_entries(reader)? {
+            if is_solution(&key, &path) && self.insert(key, path)? {
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
+
+    /// Writes the best known paths, one entry per key.
+    pub fn export(&self, mut writer: impl Write) -> io::Result<()> {
+        for (key, path) in &self.best {
+            writeln!(writer, "{}", format_entry(key, path))?;
+        }
+        Ok(())
+    }
+
+    /// Returns the best known paths ordered by key.
+    pub fn entries(&self) -> impl Iterator<Item = (&Key, &Path)> {
+        self.best.iter()
+    }
+
+    /// Returns all improvements in the order they were found, including the first path of each key.
+    pub fn improvements(&self) -> &[Improvement] {
+        &self.improvements
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::freecell::{Limits, Solver};
+
+    fn game(cells: usize) -> Game {
+        let mut game = Game::new();
+        game.set_cell_num(cells);
+        game
+    }
+
+    fn solve(seed: u64, path_max: usize) -> Option<Path> {
+        let mut solver = Solver::new();
+        solver.deal(seed);
+        let limits = Limits {
+            path_max,
+            any: true,
+            ..Limits::default()
+        };
+        solver.solve(&limits).cloned()
+    }
+
+    #[test]
+    fn entries() {
+        let key = Key::deal(173205951, &game(4));
+        let path = path_from_hex("4871317c").expect("Should be parsed!");
+        let line = format_entry(&key, &path);
+        assert_eq!("demo 173205951 freecell 4 4871317c", line);
+        assert_eq!(Some((key, path)), parse_entry(&line));
+
+        assert!(parse_entry("demo 173205951 freecell 4").is_none());
+        assert!(parse_entry("demo 173205951 freecell x 48").is_none());
+        assert!(parse_entry("demo 173205951 freecell 4 487").is_none());
+
+        let mut horne = game(3);
+        horne.set_auto_play(AutoPlay::Horne);
+        let key = Key::deal(1, &horne);
+        assert_eq!("demo 1 freecell:horne 3", key.to_string());
+        let dealt = key.game().expect("Should be dealt!");
+        assert_eq!((AutoPlay::Horne, 3), (dealt.auto_play(), dealt.cell_num()));
+        assert!(Key {
+            variant: "bakers".to_string(),
+            ..key
+        }
+        .game()
+        .is_none());
+
+        let cards = deck::deal(1);
+        assert_eq!(Key::board(&cards, &game(4)), Key::board(&cards, &game(4)));
+        assert_ne!(
+            Key::board(&cards, &game(4)),
+            Key::board(&deck::deal(2), &game(4))
+        );
+        assert_ne!(Key::board(&cards, &game(4)), Key::board(&cards, &game(3)));
+        assert_eq!(None, Key::board(&cards, &game(4)).cards());
+    }
+
+    #[test]
+    fn persistence() {
+        let file = std::env::temp_dir().join(format!("freecell-cache-{}.txt", std::process::id()));
+        let _ = std::fs::remove_file(&file);
+
+        let key = Key::deal(1, &game(4));
+        let long = solve(1, 257).expect("Should be solved!");
+        let short = solve(1, long.len()).expect("Should be improved!");
+        assert!(is_solution(&key, &long) && is_solution(&key, &short));
+        assert!(!is_solution(&Key::deal(2, &game(4)), &short));
+
+        let mut cache = Cache::open(&file).expect("Should be opened!");
+        assert!(cache
+            .insert(key.clone(), long.clone())
+            .expect("Should be written!"));
+        assert!(cache
+            .insert(key.clone(), short.clone())
+            .expect("Should be written!"));
+        assert!(!cache
+            .insert(key.clone(), long.clone())
+            .expect("Should be written!"));
+
+        let cache = Cache::open(&file).expect("Should be reopened!");
+        assert_eq!(Some(&short), cache.get(&key));
+        assert_eq!(
+            vec![long.len()],
+            cache
+                .improvements()
+                .iter()
+                .filter_map(|improvement| improvement.from)
+                .collect::<Vec<_>>()
+        );
+
+        let mut exported = Vec::new();
+        cache.export(&mut exported).expect("Should be exported!");
+        let _ = std::fs::remove_file(&file);
+
+        let mut other = Cache::open(&file).expect("Should be opened!");
+        let forged = format!("{}\n", format_entry(&Key::deal(2, &game(4)), &short));
+        assert_eq!(1, other.import(&exported[..]).expect("Should be imported!"));
+        assert_eq!(
+            0,
+            other
+                .import(forged.as_bytes())
+                .expect("Should be imported!")
+        );
+        assert_eq!(Some(&short), other.get(&key));
+        let _ = std::fs::remove_file(&file);
+
+        // A path is not kept if it can't be written.
+        let mut unwritable =
+            Cache::open(file.join("missing").join("cache.txt")).expect("Should be opened!");
+        assert!(unwritable.insert(key.clone(), short).is_err());
+        assert_eq!(None, unwritable.get(&key));
+        assert!(unwritable.improvements().is_empty());
+    }
+}
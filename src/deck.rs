@@ -2,6 +2,10 @@
 //! A standard 52-card deck comprises 13 ranks in each of the 4 French suits:
 //! spades (♠), diamonds (♦), clubs (♣) and hearts (♥).
 
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
+
 /// 13 ranks: ace, 2, 3, 4, 5, 6, 7, 8, 9, 10, jack, queen, king.
 pub const RANK_NUM: usize = 13;
 /// 4 suits: spades (♠), diamonds (♦), clubs (♣) and hearts (♥).
@@ -13,9 +17,254 @@ pub const CARD_NUM: usize = RANK_NUM * SUIT_NUM;
 pub const RANKS: [char; RANK_NUM] = [
     'A', '2', '3', '4', '5', '6', '7', '8', '9', 'T', 'J', 'Q', 'K',
 ];
-// pub const SUITS: [char; SUIT_NUM] = ['S', 'D', 'C', 'H'];
 /// An array of suits: ['♠', '♦', '♣', '♥'].
 pub const SUITS: [char; SUIT_NUM] = ['♠', '♦', '♣', '♥'];
+/// An array of suits in ASCII: ['S', 'D', 'C', 'H'].
+pub const SUITS_ASCII: [char; SUIT_NUM] = ['S', 'D', 'C', 'H'];
+
+/// A card rank, from ace to king.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Rank {
+    Ace,
+    Two,
+    Three,
+    Four,
+    Five,
+    Six,
+    Seven,
+    Eight,
+    Nine,
+    Ten,
+    Jack,
+    Queen,
+    King,
+}
+
+/// A card suit, in the order of the card encoding.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Suit {
+    Spades,
+    Diamonds,
+    Clubs,
+    Hearts,
+}
+
+/// A card color.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Color {
+    Black,
+    Red,
+}
+
+/// A playing card. Holds the compact `u8` encoding: `rank * SUIT_NUM + suit`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Card(u8);
+
+/// An error returned when parsing a card, a rank or a suit fails
+/// or when a value is out of the card encoding range.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseCardError(String);
+
+impl fmt::Display for ParseCardError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid card: '{}'", self.0)
+    }
+}
+
+impl std::error::Error for ParseCardError {}
+
+impl Rank {
+    /// All ranks, from ace to king.
+    pub const ALL: [Rank; RANK_NUM] = [
+        Rank::Ace,
+        Rank::Two,
+        Rank::Three,
+        Rank::Four,
+        Rank::Five,
+        Rank::Six,
+        Rank::Seven,
+        Rank::Eight,
+        Rank::Nine,
+        Rank::Ten,
+        Rank::Jack,
+        Rank::Queen,
+        Rank::King,
+    ];
+
+    /// Returns the rank index, from 0 for ace to 12 for king.
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    /// Returns the rank with the index, or [`None`] if the index is out of range.
+    pub fn from_index(index: usize) -> Option<Rank> {
+        Self::ALL.get(index).copied()
+    }
+}
+
+impl fmt::Display for Rank {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", RANKS[self.index()])
+    }
+}
+
+impl FromStr for Rank {
+    type Err = ParseCardError;
+
+    /// Parses "A", "2", ..., "9", "T" or "10", "J", "Q" and "K", ignoring case.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let upper = s.to_ascii_uppercase();
+        let mut chars = upper.chars();
+        let index = match (chars.next(), chars.next()) {
+            _ if upper == "10" => RANKS.iter().position(|&c| c == 'T'),
+            (Some(c), None) => RANKS.iter().position(|&r| r == c),
+            _ => None,
+        };
+        index
+            .and_then(Rank::from_index)
+            .ok_or_else(|| ParseCardError(s.to_string()))
+    }
+}
+
+impl Suit {
+    /// All suits, in the order of the card encoding.
+    pub const ALL: [Suit; SUIT_NUM] = [Suit::Spades, Suit::Diamonds, Suit::Clubs, Suit::Hearts];
+
+    /// Returns the suit index, from 0 to 3.
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    /// Returns the suit with the index, or [`None`] if the index is out of range.
+    pub fn from_index(index: usize) -> Option<Suit> {
+        Self::ALL.get(index).copied()
+    }
+
+    pub fn color(self) -> Color {
+        match self {
+            Suit::Spades | Suit::Clubs => Color::Black,
+            Suit::Diamonds | Suit::Hearts => Color::Red,
+        }
+    }
+}
+
+impl fmt::Display for Suit {
+    /// Writes the glyph, e.g. '♠', or the ASCII letter, e.g. 'S', in the alternate form.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match f.alternate() {
+            true => write!(f, "{}", SUITS_ASCII[self.index()]),
+            false => write!(f, "{}", SUITS[self.index()]),
+        }
+    }
+}
+
+impl FromStr for Suit {
+    type Err = ParseCardError;
+
+    /// Parses a glyph, e.g. "♠", or an ASCII letter, e.g. "S", ignoring case.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut chars = s.chars();
+        let index = match (chars.next(), chars.next()) {
+            (Some(c), None) => SUITS.iter().position(|&g| g == c).or_else(|| {
+                SUITS_ASCII
+                    .iter()
+                    .position(|&a| a == c.to_ascii_uppercase())
+            }),
+            _ => None,
+        };
+        index
+            .and_then(Suit::from_index)
+            .ok_or_else(|| ParseCardError(s.to_string()))
+    }
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Color::Black => f.write_str("black"),
+            Color::Red => f.write_str("red"),
+        }
+    }
+}
+
+impl Card {
+    pub fn new(rank: Rank, suit: Suit) -> Self {
+        Card(to_card(rank.index(), suit.index()))
+    }
+
+    /// Wraps an encoded card known to be valid.
+    pub(crate) fn from_u8(card: u8) -> Self {
+        debug_assert!((card as usize) < CARD_NUM);
+        Card(card)
+    }
+
+    /// Iterates over a standard 52-card deck in the encoding order.
+    pub fn all() -> impl Iterator<Item = Card> {
+        (0..CARD_NUM as u8).map(Card)
+    }
+
+    pub fn rank(self) -> Rank {
+        Rank::ALL[card_rank(self.0)]
+    }
+
+    pub fn suit(self) -> Suit {
+        Suit::ALL[card_suit(self.0)]
+    }
+
+    pub fn color(self) -> Color {
+        self.suit().color()
+    }
+
+    pub fn is_black(self) -> bool {
+        is_card_black(self.0)
+    }
+
+    pub fn is_red(self) -> bool {
+        is_card_red(self.0)
+    }
+}
+
+impl From<Card> for u8 {
+    fn from(card: Card) -> u8 {
+        card.0
+    }
+}
+
+impl TryFrom<u8> for Card {
+    type Error = ParseCardError;
+
+    fn try_from(card: u8) -> Result<Self, Self::Error> {
+        if (card as usize) < CARD_NUM {
+            Ok(Card(card))
+        } else {
+            Err(ParseCardError(card.to_string()))
+        }
+    }
+}
+
+impl fmt::Display for Card {
+    /// Writes the rank and the suit glyph, e.g. "A♠", or the ASCII letter, e.g. "AS",
+    /// in the alternate form.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match f.alternate() {
+            true => write!(f, "{}{:#}", self.rank(), self.suit()),
+            false => write!(f, "{}{}", self.rank(), self.suit()),
+        }
+    }
+}
+
+impl FromStr for Card {
+    type Err = ParseCardError;
+
+    /// Parses a rank followed by a suit, e.g. "A♠", "as", "10H" or "TH".
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || ParseCardError(s.to_string());
+        let (split, _) = s.char_indices().last().ok_or_else(error)?;
+        let rank = s[..split].parse::<Rank>().map_err(|_| error())?;
+        let suit = s[split..].parse::<Suit>().map_err(|_| error())?;
+        Ok(Card::new(rank, suit))
+    }
+}
 
 /// Returns the card index.
 pub fn to_card(rank: usize, suit: usize) -> u8 {
@@ -61,10 +310,10 @@ pub fn card_to_string(card: u8) -> String {
 /// let cards = deck::new();
 /// assert!(deck::to_string(&cards).starts_with("A♠A♦A♣A♥2♠2♦2♣2♥"));
 /// ```
-pub fn new() -> [u8; CARD_NUM] {
-    let mut deck = [0u8; CARD_NUM];
-    for i in 0..CARD_NUM {
-        deck[i] = i as u8;
+pub fn new() -> [Card; CARD_NUM] {
+    let mut deck = [Card(0); CARD_NUM];
+    for (i, card) in deck.iter_mut().enumerate() {
+        *card = Card(i as u8);
     }
     deck
 }
@@ -80,10 +329,10 @@ pub fn new() -> [u8; CARD_NUM] {
 ///
 /// assert!(deck::to_string(&cards).starts_with("K♦3♠4♠J♠T♥7♠K♠A♠"));
 /// ```
-pub fn shuffle(cards: &mut [u8], mut seed: u64) {
+pub fn shuffle<T>(cards: &mut [T], mut seed: u64) {
     let m = 0x80000000u64 as f64;
-    let a = 1103515245u64 as f64;
-    let c = 12345u64 as f64;
+    let a = 1103515245_f64;
+    let c = 12345_f64;
     let len = cards.len();
 
     for i in 0..len {
@@ -92,23 +341,45 @@ pub fn shuffle(cards: &mut [u8], mut seed: u64) {
         // swap cards
         let j = (seed % len as u64) as usize;
         if i != j {
-            let card = cards[i];
-            cards[i] = cards[j];
-            cards[j] = card;
+            cards.swap(i, j);
         }
     }
 }
 
 /// Creates a standard 52-card deck and shuffles it.
-pub fn deal(seed: u64) -> [u8; CARD_NUM] {
+pub fn deal(seed: u64) -> [Card; CARD_NUM] {
     let mut cards = new();
     shuffle(&mut cards, seed);
     cards
 }
 
 /// Formats a card array into a [`String`], e.g. "A♠2♦3♣4♥".
-pub fn to_string(cards: &[u8]) -> String {
-    cards.iter().map(|&c| card_to_string(c)).collect()
+pub fn to_string(cards: &[Card]) -> String {
+    cards.iter().map(|c| c.to_string()).collect()
+}
+
+/// Parses a card sequence, e.g. "A♠2♦3♣4♥" or "AS 2D 3C 4H".
+/// Whitespace and commas between cards are ignored.
+pub fn parse(s: &str) -> Result<Vec<Card>, ParseCardError> {
+    let mut cards = Vec::new();
+    let mut start = None;
+    for (i, c) in s.char_indices() {
+        if c.is_whitespace() || c == ',' {
+            if let Some(from) = start {
+                return Err(ParseCardError(s[from..i].to_string()));
+            }
+        } else {
+            let from = *start.get_or_insert(i);
+            if SUITS.contains(&c) || SUITS_ASCII.contains(&c.to_ascii_uppercase()) {
+                cards.push(s[from..i + c.len_utf8()].parse()?);
+                start = None;
+            }
+        }
+    }
+    match start {
+        Some(from) => Err(ParseCardError(s[from..].to_string())),
+        None => Ok(cards),
+    }
 }
 
 #[cfg(test)]
@@ -151,6 +422,56 @@ mod tests {
         }
     }
 
+    #[test]
+    fn typed_cards() {
+        assert_eq!(CARD_NUM, Card::all().count());
+        for (index, card) in Card::all().enumerate() {
+            let value = u8::from(card);
+            assert_eq!(index, value as usize);
+            assert_eq!(Ok(card), Card::try_from(value));
+            assert_eq!(card_rank(value), card.rank().index());
+            assert_eq!(card_suit(value), card.suit().index());
+            assert_eq!(is_card_black(value), card.color() == Color::Black);
+            assert_eq!(card, Card::new(card.rank(), card.suit()));
+
+            assert_eq!(card_to_string(value), card.to_string());
+            assert_eq!(Ok(card), card.to_string().parse());
+            assert_eq!(Ok(card), format!("{:#}", card).parse());
+            assert_eq!(Ok(card), format!("{:#}", card).to_lowercase().parse());
+        }
+        assert!(Card::try_from(CARD_NUM as u8).is_err());
+
+        assert_eq!("AS", format!("{:#}", Card::new(Rank::Ace, Suit::Spades)));
+        assert_eq!(Ok(Card::new(Rank::Ten, Suit::Hearts)), "10♥".parse());
+        assert_eq!(Ok(Card::new(Rank::Ten, Suit::Hearts)), "TH".parse());
+        assert!("".parse::<Card>().is_err());
+        assert!("A".parse::<Card>().is_err());
+        assert!("1S".parse::<Card>().is_err());
+        assert!("AX".parse::<Card>().is_err());
+        assert!("A♠♠".parse::<Card>().is_err());
+
+        assert_eq!(RANK_NUM, Rank::ALL.len());
+        assert_eq!(Some(Rank::King), Rank::from_index(RANK_NUM - 1));
+        assert_eq!(None, Rank::from_index(RANK_NUM));
+        assert_eq!(Color::Red, Suit::Diamonds.color());
+        assert_eq!(Some(Suit::Hearts), Suit::from_index(SUIT_NUM - 1));
+    }
+
+    #[test]
+    fn parsing() {
+        let cards = deal(1377011176);
+        assert_eq!(Ok(cards.to_vec()), parse(&to_string(&cards)));
+
+        let ascii: Vec<String> = cards.iter().map(|c| format!("{:#}", c)).collect();
+        assert_eq!(Ok(cards.to_vec()), parse(&ascii.join(" ")));
+        assert_eq!(Ok(cards.to_vec()), parse(&ascii.join(",")));
+
+        assert_eq!(Ok(vec![]), parse(" "));
+        assert!(parse("A♠2").is_err());
+        assert!(parse("A 2♦").is_err());
+        assert!(parse("X♠").is_err());
+    }
+
     #[test]
     fn creation() {
         let mut cards = new();
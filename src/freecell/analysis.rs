@@ -0,0 +1,65 @@
+//! # Deal analysis
+
+use std::time::Instant;
+
+use crate::deck;
+use crate::freecell::basis::CELL_NUM;
+use crate::freecell::game::{Game, Path};
+use crate::freecell::solver::{Limits, Solver};
+
+/// Finds the smallest number of cells a deal can be solved with.
+/// The solver is run with 0, 1, 2, 3 and 4 available cells, blocking the remaining ones.
+/// Returns the cell count and its solution path, or [`None`] if no solution is found within the limits.
+/// The time limit is shared by all the runs.
+pub fn min_cells(seed: u64, limits: &Limits) -> Option<(usize, Path)> {
+    let mut game = Game::new();
+    game.deal(&deck::deal(seed));
+    min_cells_from(&game, limits)
+}
+
+/// Finds the smallest number of cells the game can be solved with from its current position,
+/// see [`min_cells`]. Found paths are relative to that position.
+pub fn min_cells_from(game: &Game, limits: &Limits) -> Option<(usize, Path)> {
+    // Note: The clock is read only if needed, it's not available on all targets (e.g. wasm32).
+    let deadline = limits.time_max.map(|time| (Instant::now(), time));
+    let mut limits = limits.clone();
+    let mut solver = Solver::new();
+    solver.set_auto_play(game.auto_play());
+    for cell_num in 0..=CELL_NUM {
+        if let Some((start, time)) = deadline {
+            limits.time_max = Some(time.checked_sub(start.elapsed())?);
+        }
+        solver.set_cell_num(cell_num);
+        solver.start_from(game);
+        if let Some(path) = solver.solve(&limits) {
+            return Some((cell_num, path.clone()));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::freecell::basis::is_cell;
+
+    #[test]
+    fn min_cells_of_deal() {
+        let limits = Limits {
+            any: true,
+            ..Limits::default()
+        };
+        let (cell_num, path) = min_cells(1, &limits).expect("Should be solved!");
+        assert_eq!(2, cell_num);
+
+        let mut game = Game::new();
+        game.set_cell_num(cell_num);
+        game.deal(&deck::deal(1));
+        for mv in &path {
+            let taker = mv.taker().index();
+            assert!(!is_cell(taker) || game.open_cell_range().contains(&taker));
+            game.apply(mv);
+        }
+        assert!(game.is_done());
+    }
+}
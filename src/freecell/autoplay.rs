@@ -0,0 +1,176 @@
+//! # Auto-play policies
+//! [`Game::move_cards_auto`] moves cards to bases as long as the [`AutoPlay`] policy of the game allows it.
+//! Each card goes to its base exactly once in any solution, so playing it there early never makes a solution longer.
+//! It may make one impossible though: the card could be needed on the tableau to hold an opposite color card.
+//! A policy is *safe* if it only moves cards no solution needs any more, which keeps the optimal solutions reachable.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::deck::{Card, Suit};
+use crate::freecell::game::{BaseRanks, Game};
+
+/// Which cards are moved to bases automatically.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum AutoPlay {
+    /// No card: every move is made by the player. Safe.
+    None,
+    /// Cards up to two ranks above the lowest opposite color base, see [`BaseRanks::ge`].
+    /// The rule the solver is tuned for, not safe: a shorter solution may still build on the card.
+    #[default]
+    Standard,
+    /// Aces and twos, and cards whose opposite color cards of the rank below are both in bases,
+    /// as in the original Microsoft FreeCell. Safe.
+    Microsoft,
+    /// The Microsoft rule, and cards whose opposite color cards two ranks below and the same color card
+    /// three ranks below are in bases. Safe.
+    Horne,
+    /// Any card that can go to its base. Not safe.
+    Aggressive,
+}
+
+impl AutoPlay {
+    /// All policies, from the most to the least cautious.
+    pub const ALL: [AutoPlay; 5] = [
+        AutoPlay::None,
+        AutoPlay::Microsoft,
+        AutoPlay::Horne,
+        AutoPlay::Standard,
+        AutoPlay::Aggressive,
+    ];
+
+    /// Returns [`true`] if the policy never moves a card a solution could still need.
+    pub fn is_safe(self) -> bool {
+        matches!(self, AutoPlay::None | AutoPlay::Microsoft | AutoPlay::Horne)
+    }
+
+    /// Returns [`true`] if the policy lets the card go to its base, which is expected to be possible.
+    /// The base ranks are the lowest ones of each color in the game, see [`Game::base_min_ranks`].
+    pub(crate) fn allows(self, game: &Game, ranks: &BaseRanks, card: Card) -> bool {
+        let rank = card.rank().index() + 1;
+        let opposite_min = ranks.next_rank(card) - 1;
+        let microsoft = rank <= 2 || rank <= opposite_min + 1;
+
+        match self {
+            AutoPlay::None => false,
+            AutoPlay::Standard => ranks.ge(card),
+            AutoPlay::Microsoft => microsoft,
+            AutoPlay::Horne => {
+                // The number of cards in the base of the other suit of the same color.
+                let suit = card.suit();
+                let same_color = Suit::ALL
+                    .iter()
+                    .find(|&&other| other != suit && other.color() == suit.color())
+                    .map_or(0, |&other| game.base_len(other));
+                microsoft || (rank <= opposite_min + 2 && rank <= same_color + 3)
+            }
+            AutoPlay::Aggressive => true,
+        }
+    }
+}
+
+/// The error returned when an auto-play policy can't be parsed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseAutoPlayError(String);
+
+impl fmt::Display for ParseAutoPlayError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid auto-play policy: '{}'", self.0)
+    }
+}
+
+impl std::error::Error for ParseAutoPlayError {}
+
+impl fmt::Display for AutoPlay {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            AutoPlay::None => "none",
+            AutoPlay::Standard => "standard",
+            AutoPlay::Microsoft => "microsoft",
+            AutoPlay::Horne => "horne",
+            AutoPlay::Aggressive => "aggressive",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for AutoPlay {
+    type Err = ParseAutoPlayError;
+
+    /// Parses "none", "standard", "microsoft", "horne" or "aggressive", ignoring case.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        AutoPlay::ALL
+            .iter()
+            .copied()
+            .find(|policy| policy.to_string().eq_ignore_ascii_case(s))
+            .ok_or_else(|| ParseAutoPlayError(s.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deck;
+    use crate::freecell::{Limits, Solver};
+
+    #[test]
+    fn policies() {
+        for policy in AutoPlay::ALL {
+            assert_eq!(Ok(policy), policy.to_string().parse());
+        }
+        assert!("horn".parse::<AutoPlay>().is_err());
+
+        // Deal #173205951 starts with an ace on top of a pile, which only stays there without auto-play.
+        let start = |policy| {
+            let mut game = Game::new();
+            game.set_auto_play(policy);
+            game.deal(&deck::deal(173205951));
+            game
+        };
+        let mut none = start(AutoPlay::None);
+        assert_eq!(0, none.move_cards_auto());
+        for policy in &AutoPlay::ALL[1..] {
+            assert_eq!(1, start(*policy).move_cards_auto());
+        }
+
+        // Along a solution, each policy moves at least the cards the more cautious ones do.
+        let mut solver = Solver::new();
+        solver.deal(1);
+        let limits = Limits {
+            any: true,
+            ..Limits::default()
+        };
+        let path = solver.solve(&limits).expect("Should be solved!").clone();
+        let mut game = start(AutoPlay::None);
+        game.deal(&deck::deal(1));
+        for mv in &path {
+            let counts: Vec<usize> = AutoPlay::ALL
+                .iter()
+                .map(|&policy| {
+                    let mut copy = game.clone();
+                    copy.set_auto_play(policy);
+                    copy.move_cards_auto()
+                })
+                .collect();
+            assert!(counts.windows(2).all(|pair| pair[0] <= pair[1]));
+            game.apply(mv);
+        }
+        assert!(game.is_done());
+
+        // Solutions found with any policy are valid moves, whatever the policy of the replaying game.
+        for policy in [AutoPlay::None, AutoPlay::Microsoft, AutoPlay::Aggressive] {
+            let mut solver = Solver::new();
+            solver.set_auto_play(policy);
+            solver.deal(1);
+            let path = solver.solve(&limits).expect("Should be solved!").clone();
+            none.deal(&deck::deal(1));
+            for mv in &path {
+                assert!(none.is_valid_move(mv.giver().index(), mv.taker().index()));
+                none.apply(mv);
+            }
+            assert!(none.is_done());
+        }
+    }
+}
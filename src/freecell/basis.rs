@@ -1,7 +1,8 @@
 //! # FreeCell layout
 
-use crate::deck;
+use crate::deck::Card;
 use core::ops::Range;
+use std::fmt;
 
 /// There are 4 open *foundations*.
 pub const BASE_NUM: usize = 4; // foundation piles
@@ -21,6 +22,74 @@ pub const CELL_END: usize = CELL_START + CELL_NUM;
 pub const PILE_START: usize = CELL_END;
 pub const PILE_END: usize = PILE_START + PILE_NUM;
 
+/// A typed desk position. Holds a zero-based number within its kind.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Spot {
+    Foundation(usize),
+    Cell(usize),
+    Cascade(usize),
+}
+
+impl Spot {
+    /// Returns the spot at the desk index, or [`None`] if the index is out of the desk.
+    pub fn from_index(index: usize) -> Option<Spot> {
+        if is_base(index) {
+            Some(Spot::Foundation(index - BASE_START))
+        } else if is_cell(index) {
+            Some(Spot::Cell(index - CELL_START))
+        } else if is_pile(index) {
+            Some(Spot::Cascade(index - PILE_START))
+        } else {
+            None
+        }
+    }
+
+    /// Returns the desk index of the spot.
+    pub fn index(self) -> usize {
+        match self {
+            Spot::Foundation(n) => BASE_START + n,
+            Spot::Cell(n) => CELL_START + n,
+            Spot::Cascade(n) => PILE_START + n,
+        }
+    }
+
+    /// Returns [`true`] if the number is within its kind.
+    pub fn is_valid(self) -> bool {
+        match self {
+            Spot::Foundation(n) => n < BASE_NUM,
+            Spot::Cell(n) => n < CELL_NUM,
+            Spot::Cascade(n) => n < PILE_NUM,
+        }
+    }
+
+    /// Returns the hex digit of the spot, see [`spot_to_hex`].
+    pub fn to_hex(self) -> char {
+        spot_to_hex(self.index()).chars().next().unwrap_or('?')
+    }
+
+    /// Returns the spot of the hex digit, see [`hex_to_spot`].
+    pub fn from_hex(digit: char) -> Option<Spot> {
+        hex_to_spot(digit).and_then(Spot::from_index)
+    }
+}
+
+impl From<Spot> for usize {
+    fn from(spot: Spot) -> usize {
+        spot.index()
+    }
+}
+
+impl fmt::Display for Spot {
+    /// Writes the spot name, e.g. "base 1", "cell 2" or "pile 3".
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Spot::Foundation(n) => write!(f, "base {}", 1 + n),
+            Spot::Cell(n) => write!(f, "cell {}", 1 + n),
+            Spot::Cascade(n) => write!(f, "pile {}", 1 + n),
+        }
+    }
+}
+
 pub fn desk_range() -> Range<usize> {
     0..DESK_SIZE
 }
@@ -58,21 +127,15 @@ pub fn is_cell(index: usize) -> bool {
 }
 
 pub fn spot_name(index: usize) -> String {
-    if is_base(index) {
-        return format!("base {}", 1 + index - BASE_START);
+    match Spot::from_index(index) {
+        Some(spot) => spot.to_string(),
+        None => format!("unknown {}", index),
     }
-    if is_pile(index) {
-        return format!("pile {}", 1 + index - PILE_START);
-    }
-    if is_cell(index) {
-        return format!("cell {}", 1 + index - CELL_START);
-    }
-    format!("unknown {}", index)
 }
 
 pub fn spot_to_hex(mut index: usize) -> String {
     if is_pile(index) {
-        index = index - PILE_START;
+        index -= PILE_START;
     } else if is_base(index) {
         index = index - BASE_START + PILE_NUM;
     } else if is_cell(index) {
@@ -82,16 +145,28 @@ pub fn spot_to_hex(mut index: usize) -> String {
     format!("{:x}", index)
 }
 
+/// Inverse of [`spot_to_hex`]. Returns [`None`] for an unknown digit.
+pub fn hex_to_spot(digit: char) -> Option<usize> {
+    let index = digit.to_digit(16)? as usize;
+    if index < PILE_NUM {
+        Some(PILE_START + index)
+    } else if index < PILE_NUM + BASE_NUM {
+        Some(BASE_START + index - PILE_NUM)
+    } else {
+        Some(CELL_START + index - PILE_NUM - BASE_NUM)
+    }
+}
+
 /// Returns [`true`] if cards can form a tableau.
 /// Tableaux must be built down by alternating colors.
-pub fn is_tableau(card_a: u8, card_b: u8) -> bool {
-    deck::card_rank(card_a) == deck::card_rank(card_b) + 1
-        && deck::card_color(card_a) != deck::card_color(card_b)
+pub fn is_tableau(card_a: Card, card_b: Card) -> bool {
+    card_a.rank().index() == card_b.rank().index() + 1 && card_a.color() != card_b.color()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::deck::{Rank, Suit};
 
     #[test]
     fn ranges() {
@@ -122,11 +197,40 @@ mod tests {
         }
     }
 
+    #[test]
+    fn hex() {
+        for spot in desk_range() {
+            let hex = spot_to_hex(spot);
+            assert_eq!(1, hex.len());
+            assert_eq!(Some(spot), hex_to_spot(hex.chars().next().unwrap()));
+        }
+        assert_eq!(None, hex_to_spot('x'));
+    }
+
+    #[test]
+    fn typed_spots() {
+        for index in desk_range() {
+            let spot = Spot::from_index(index).expect("Should be on the desk!");
+            assert!(spot.is_valid());
+            assert_eq!(index, spot.index());
+            assert_eq!(index, usize::from(spot));
+            assert_eq!(spot_name(index), spot.to_string());
+            assert_eq!(spot_to_hex(index), spot.to_hex().to_string());
+            assert_eq!(Some(spot), Spot::from_hex(spot.to_hex()));
+            assert_eq!(is_base(index), matches!(spot, Spot::Foundation(_)));
+            assert_eq!(is_cell(index), matches!(spot, Spot::Cell(_)));
+            assert_eq!(is_pile(index), matches!(spot, Spot::Cascade(_)));
+        }
+        assert_eq!(None, Spot::from_index(DESK_SIZE));
+        assert!(!Spot::Cell(CELL_NUM).is_valid());
+        assert_eq!("pile 8", Spot::Cascade(7).to_string());
+    }
+
     #[test]
     fn tableaux() {
-        let a = deck::to_card(1, 2);
-        let b = deck::to_card(2, 2);
-        let c = deck::to_card(2, 3);
+        let a = Card::new(Rank::Two, Suit::Clubs);
+        let b = Card::new(Rank::Three, Suit::Clubs);
+        let c = Card::new(Rank::Three, Suit::Hearts);
 
         assert!(!is_tableau(a, b));
         assert!(!is_tableau(b, a));
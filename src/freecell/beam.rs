@@ -0,0 +1,139 @@
+//! # Beam search
+//! A fast, incomplete alternative to [`Solver`](crate::freecell::Solver): positions are expanded
+//! one move deeper at a time, keeping only the best ones of each depth by [`position_score`].
+//! It finds longer solutions and misses some, but takes bounded memory and time per depth.
+
+use std::collections::HashSet;
+
+use crate::freecell::game::{Game, Path};
+use crate::freecell::invariant::Key64;
+use crate::freecell::solver::position_score;
+
+/// Beam search settings.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct Beam {
+    /// The number of positions kept per depth on the first try.
+    pub width: usize,
+    /// The factor the width is multiplied by after a failed try.
+    pub widen: usize,
+    /// The largest width to be tried.
+    pub width_max: usize,
+    /// The upper bound of the solution length (exclusive).
+    pub path_max: usize,
+}
+
+impl Default for Beam {
+    fn default() -> Self {
+        Self {
+            width: 100,
+            widen: 4,
+            width_max: 10000,
+            path_max: 257,
+        }
+    }
+}
+
+impl Beam {
+    /// Searches with the width widened after each failure, up to the largest one.
+    /// Returns the path found, relative to the position, and the width it was found with.
+    pub fn solve(&self, game: &Game) -> Option<(Path, usize)> {
+        let mut width = self.width.max(1);
+        loop {
+            if let Some(path) = self.search(game, width) {
+                return Some((path, width));
+            }
+            let wider = width.saturating_mul(self.widen).min(self.width_max);
+            if wider <= width {
+                return None;
+            }
+            width = wider;
+        }
+    }
+
+    /// Searches keeping `width` positions per depth.
+    /// Returns the shortest path found at the first depth any is found, relative to the position.
+    pub fn search(&self, game: &Game, width: usize) -> Option<Path> {
+        let mut game = game.clone();
+        let origin = game.path().len();
+        game.move_cards_auto();
+        if game.is_done() {
+            return Some(game.path()[origin..].to_vec());
+        }
+
+        let mut done: HashSet<Key64> = HashSet::new();
+        done.insert(game.get_invariant());
+        let mut layer = vec![game.path().clone()];
+
+        while !layer.is_empty() {
+            let mut next = Vec::new();
+            let mut solution: Option<Path> = None;
+            for path in &layer {
+                game.set_path(path.iter());
+                let mark = path.len();
+                for mv in game.get_all_moves() {
+                    game.backward(mark);
+                    game.apply(&mv);
+                    game.move_cards_auto();
+
+                    let len = game.path().len() - origin;
+                    if game.is_done() {
+                        if len < self.path_max
+                            && solution.as_ref().is_none_or(|best| len < best.len())
+                        {
+                            solution = Some(game.path()[origin..].to_vec());
+                        }
+                    } else if game.estimate_path_len() - origin < self.path_max
+                        && game.has_next_move()
+                        && done.insert(game.get_invariant())
+                    {
+                        next.push((position_score(&game), len, game.path().clone()));
+                    }
+                }
+            }
+            if solution.is_some() {
+                return solution;
+            }
+
+            // Keep the best positions, the ones with shorter paths on ties.
+            if next.len() > width {
+                next.select_nth_unstable_by_key(width, |&(score, len, _)| (score, len));
+                next.truncate(width);
+            }
+            layer = next.into_iter().map(|(_, _, path)| path).collect();
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deck;
+
+    #[test]
+    fn search() {
+        let beam = Beam::default();
+        for seed in 1..=5 {
+            let mut game = Game::new();
+            game.deal(&deck::deal(seed));
+            let (path, width) = beam.solve(&game).expect("Should be solved!");
+            assert!(width >= beam.width && width <= beam.width_max);
+
+            game.forward(path.iter());
+            assert!(game.is_done());
+        }
+
+        // Too narrow and too short to succeed.
+        let beam = Beam {
+            width: 1,
+            widen: 2,
+            width_max: 4,
+            path_max: 60,
+        };
+        let mut game = Game::new();
+        game.deal(&deck::deal(1));
+        assert_eq!(None, beam.solve(&game));
+    }
+}
@@ -1,12 +1,16 @@
 use crate::deck;
+use crate::deck::{Card, Suit};
+use crate::freecell::autoplay::AutoPlay;
 use crate::freecell::basis::*;
 use crate::freecell::invariant::Key64;
+use crate::freecell::ordering::{MoveOrder, MoveSorter};
 use crate::util::Consumer;
 use crate::util::SingleConsumer;
 use crate::util::TotalConsumer;
+use core::ops::Range;
 
 /// Represents a step in the game, where a card is moved from a giver's position to a taker's position.
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Move(u8, u8);
 
 pub type MoveConsumer = TotalConsumer<Move>;
@@ -17,26 +21,111 @@ type Pile = Vec<u8>;
 type Desk = Vec<Pile>;
 
 /// A freecell game object.
+#[derive(Clone)]
 pub struct Game {
     desk: Desk,
     path: Path,
+    cell_num: usize,
+    auto_play: AutoPlay,
 }
 
 impl Move {
-    pub fn new(giver: usize, taker: usize) -> Self {
+    /// Returns [`None`] for spots out of the desk or a move to the same spot.
+    pub fn new(giver: Spot, taker: Spot) -> Option<Self> {
+        if giver.is_valid() && taker.is_valid() && giver != taker {
+            Some(Self::at(giver.index(), taker.index()))
+        } else {
+            None
+        }
+    }
+
+    pub(crate) fn at(giver: usize, taker: usize) -> Self {
         // Note: Casting down to u8 to save memory!
         Self(giver as u8, taker as u8)
     }
 
-    pub fn giver(&self) -> usize {
-        self.0 as usize
+    pub fn giver(&self) -> Spot {
+        Spot::from_index(self.0 as usize).expect("giver on the desk")
     }
 
-    pub fn taker(&self) -> usize {
-        self.1 as usize
+    pub fn taker(&self) -> Spot {
+        Spot::from_index(self.1 as usize).expect("taker on the desk")
     }
 }
 
+/// Formats a path as a string of hex digit pairs, e.g. "4871317c".
+pub fn path_to_hex(path: &Path) -> String {
+    let mut buf = String::with_capacity(path.len() * 2);
+    for mv in path {
+        buf.push(mv.giver().to_hex());
+        buf.push(mv.taker().to_hex());
+    }
+    buf
+}
+
+/// Parses a path formatted by [`path_to_hex`].
+pub fn path_from_hex(hex: &str) -> Option<Path> {
+    let spots = hex
+        .chars()
+        .map(hex_to_spot)
+        .collect::<Option<Vec<usize>>>()?;
+    if spots.len() % 2 != 0 {
+        return None;
+    }
+    Some(spots.chunks(2).map(|mv| Move::at(mv[0], mv[1])).collect())
+}
+
+/// Returns the link showing the hex path of the deal in the FreeCell demo.
+pub fn demo_link(deal: u64, hex: &str) -> String {
+    format!(
+        "https://constf1.github.io/angular/freecell-demo?deal={}&path={}",
+        deal, hex
+    )
+}
+
+/// Parses a board: either the piles one per line, from the bottom card to the top one,
+/// or the whole deck in the deal order (e.g. as formatted by [`deck::to_string`]).
+/// Only the initial layout is accepted: 52 distinct cards, 7 in the first four piles and 6 in the others.
+pub fn parse_board(text: &str) -> Option<Vec<Card>> {
+    let lines: Vec<Vec<Card>> = text
+        .lines()
+        .map(deck::parse)
+        .collect::<Result<Vec<_>, _>>()
+        .ok()?
+        .into_iter()
+        .filter(|line| !line.is_empty())
+        .collect();
+
+    let cards: Vec<Card> = match lines.len() {
+        1 => lines.into_iter().next()?,
+        PILE_NUM => {
+            if lines.iter().map(Vec::len).ne((0..PILE_NUM).map(dealt_len)) {
+                return None;
+            }
+            (0..deck::CARD_NUM)
+                .map(|i| lines[i % PILE_NUM][i / PILE_NUM])
+                .collect()
+        }
+        _ => return None,
+    };
+
+    Some(cards).filter(|cards| is_deck(cards))
+}
+
+/// Returns the number of cards [`Game::deal`] puts on the pile.
+pub(crate) fn dealt_len(pile: usize) -> usize {
+    (deck::CARD_NUM - pile).div_ceil(PILE_NUM)
+}
+
+/// Returns [`true`] if the cards are the distinct cards of a whole deck.
+pub(crate) fn is_deck(cards: &[Card]) -> bool {
+    let mut seen = [false; deck::CARD_NUM];
+    cards.len() == deck::CARD_NUM
+        && cards
+            .iter()
+            .all(|&card| !std::mem::replace(&mut seen[u8::from(card) as usize], true))
+}
+
 pub struct BaseRanks(usize, usize);
 
 impl BaseRanks {
@@ -45,31 +134,81 @@ impl BaseRanks {
     }
 
     /// Tests if next rank greater than or equal to card rank.
-    pub fn ge(&self, card: u8) -> bool {
-        self.next_rank(card) >= deck::card_rank(card)
+    pub fn ge(&self, card: Card) -> bool {
+        self.next_rank(card) >= card.rank().index()
     }
 
     /// Returns opposite color rank + 1
-    pub fn next_rank(&self, card: u8) -> usize {
-        1 + match deck::is_card_black(card) {
+    pub fn next_rank(&self, card: Card) -> usize {
+        1 + match card.is_black() {
             true => self.1,
             false => self.0,
         }
     }
 }
 
+impl Default for Game {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Game {
     pub fn new() -> Self {
         Self {
             desk: desk_range().map(|_| Vec::new()).collect(),
             path: Path::new(),
+            cell_num: CELL_NUM,
+            auto_play: AutoPlay::default(),
         }
     }
 
+    /// Returns the number of cells available for play.
+    pub fn cell_num(&self) -> usize {
+        self.cell_num
+    }
+
+    /// Limits the number of cells available for play, blocking the remaining ones.
+    /// Values greater than [`CELL_NUM`] are clamped.
+    pub fn set_cell_num(&mut self, cell_num: usize) {
+        self.cell_num = cell_num.min(CELL_NUM);
+    }
+
+    /// Returns the policy of [`Game::move_cards_auto`].
+    pub fn auto_play(&self) -> AutoPlay {
+        self.auto_play
+    }
+
+    /// Sets which cards [`Game::move_cards_auto`] moves to bases, see [`AutoPlay`].
+    pub fn set_auto_play(&mut self, auto_play: AutoPlay) {
+        self.auto_play = auto_play;
+    }
+
+    /// Returns the range of cells available for play.
+    pub fn open_cell_range(&self) -> Range<usize> {
+        CELL_START..CELL_START + self.cell_num
+    }
+
+    /// Returns the compact card storage: piles of encoded cards indexed by spots.
     pub fn desk(&self) -> &Desk {
         &self.desk
     }
 
+    /// Iterates over the cards at the spot, from the bottom to the top.
+    pub fn cards_at(&self, spot: usize) -> impl Iterator<Item = Card> + '_ {
+        self.desk[spot].iter().map(|&card| Card::from_u8(card))
+    }
+
+    /// Iterates over the cards of a cascade, cell or foundation, from the bottom to the top.
+    pub fn pile(&self, spot: Spot) -> impl Iterator<Item = Card> + '_ {
+        self.cards_at(spot.index())
+    }
+
+    /// Returns the number of cards on the foundation of the suit.
+    pub fn base_len(&self, suit: Suit) -> usize {
+        self.desk[BASE_START + suit.index()].len()
+    }
+
     pub fn path(&self) -> &Path {
         &self.path
     }
@@ -85,25 +224,37 @@ impl Game {
         }
     }
 
-    pub fn deal(&mut self, cards: &[u8]) {
+    /// Takes the current position of another game as the starting one.
+    /// The path is cleared, the number of available cells is kept.
+    pub fn set_position(&mut self, other: &Game) {
+        self.path.clear();
+        self.desk.clone_from(&other.desk);
+    }
+
+    pub fn deal(&mut self, cards: &[Card]) {
         self.clear();
-        for (index, card) in cards.iter().enumerate() {
-            self.desk[PILE_START + index % PILE_NUM].push(*card);
+        for (index, &card) in cards.iter().enumerate() {
+            self.desk[PILE_START + index % PILE_NUM].push(u8::from(card));
         }
     }
 
     pub fn move_card(&mut self, giver: usize, taker: usize) {
         let c = self.desk[giver].pop().expect("empty giver");
         self.desk[taker].push(c);
-        self.path.push(Move::new(giver, taker));
+        self.path.push(Move::at(giver, taker));
+    }
+
+    /// Moves a card from the move giver to its taker.
+    pub fn apply(&mut self, mv: &Move) {
+        self.move_card(mv.0 as usize, mv.1 as usize);
     }
 
     pub fn backward(&mut self, mark: usize) {
         while self.path.len() > mark {
             // move destination => source
             if let Some(mv) = self.path.pop() {
-                let card = self.desk[mv.taker()].pop().expect("empty taker");
-                self.desk[mv.giver()].push(card);
+                let card = self.desk[mv.1 as usize].pop().expect("empty taker");
+                self.desk[mv.0 as usize].push(card);
             }
         }
     }
@@ -114,7 +265,7 @@ impl Game {
 
     pub fn forward<'a, T: Iterator<Item = &'a Move>>(&mut self, iter: T) {
         for mv in iter {
-            self.move_card(mv.giver(), mv.taker());
+            self.apply(mv);
         }
     }
 
@@ -125,11 +276,32 @@ impl Game {
 
     pub fn is_move_forward(&self, giver: usize, taker: usize) -> bool {
         match self.path.last() {
-            Some(mv) => mv.giver() != taker || mv.taker() != giver,
+            Some(mv) => mv.0 as usize != taker || mv.1 as usize != giver,
             None => true,
         }
     }
 
+    /// Returns [`true`] if the top card of the giver can be moved to the taker.
+    pub fn is_valid_move(&self, giver: usize, taker: usize) -> bool {
+        if giver == taker || giver >= DESK_SIZE || taker >= DESK_SIZE {
+            return false;
+        }
+        let card = match self.card_at(giver) {
+            Some(card) => card,
+            None => return false,
+        };
+        if is_base(taker) {
+            self.get_base(card) == Some(taker)
+        } else if is_cell(taker) {
+            self.open_cell_range().contains(&taker) && self.desk[taker].is_empty()
+        } else {
+            match self.card_at(taker) {
+                Some(pile_card) => is_tableau(pile_card, card),
+                None => true,
+            }
+        }
+    }
+
     pub fn base_min_ranks(&self) -> BaseRanks {
         let mut black = deck::RANK_NUM;
         let mut red = deck::RANK_NUM;
@@ -141,17 +313,22 @@ impl Game {
                 red = std::cmp::min(red, rank);
             }
         }
-        return BaseRanks::new(black, red);
+        BaseRanks::new(black, red)
     }
 
+    /// Moves cards to bases while the auto-play policy allows it, see [`Game::set_auto_play`].
+    /// Returns the number of cards moved.
     pub fn move_cards_auto(&mut self) -> usize {
         let count = self.path.len();
+        if self.auto_play == AutoPlay::None {
+            return 0;
+        }
 
         'search_start: loop {
             let ranks = self.base_min_ranks();
             for giver in play_range() {
-                if let Some(&card) = self.card_at(giver) {
-                    if ranks.ge(card) {
+                if let Some(card) = self.card_at(giver) {
+                    if self.auto_play.allows(self, &ranks, card) {
                         if let Some(taker) = self.get_base(card) {
                             self.move_card(giver, taker);
                             continue 'search_start;
@@ -168,19 +345,16 @@ impl Game {
 
         for giver in pile_range() {
             for (index, &card) in self.desk[giver].iter().rev().enumerate() {
-                if self.get_base(card).is_some() {
+                if self.get_base(Card::from_u8(card)).is_some() {
                     let mut change = true;
                     if let Some((next_giver, next_index, next_card)) = next_move {
-                        if next_index < index {
+                        if next_index < index
+                            || (next_index == index
+                                && (next_card < card
+                                    || (next_card == card
+                                        && self.desk[next_giver].len() < self.desk[giver].len())))
+                        {
                             change = false;
-                        } else if next_index == index {
-                            if next_card < card {
-                                change = false;
-                            } else if next_card == card {
-                                if self.desk[next_giver].len() < self.desk[giver].len() {
-                                    change = false;
-                                }
-                            }
                         }
                     }
                     if change {
@@ -196,7 +370,7 @@ impl Game {
     pub fn get_flat_move(&self) -> Option<(usize, usize)> {
         // 1. Move cards to bases.
         for giver in play_range() {
-            if let Some(&card) = self.card_at(giver) {
+            if let Some(card) = self.card_at(giver) {
                 if let Some(taker) = self.get_base(card) {
                     return Some((giver, taker));
                 }
@@ -221,7 +395,9 @@ impl Game {
     }
 
     pub fn count_empty_cells(&self) -> usize {
-        cell_range().filter(|&i| self.desk[i].is_empty()).count()
+        self.open_cell_range()
+            .filter(|&i| self.desk[i].is_empty())
+            .count()
     }
 
     pub fn count_empty_piles(&self) -> usize {
@@ -238,7 +414,7 @@ impl Game {
 
     pub fn is_done(&self) -> bool {
         for i in play_range() {
-            if self.desk[i].len() > 0 {
+            if !self.desk[i].is_empty() {
                 return false;
             }
         }
@@ -250,8 +426,7 @@ impl Game {
         let card_a_rank = deck::card_rank(card_a);
         let card_a_suit = deck::card_suit(card_a);
 
-        for prev_index in 0..card_index {
-            let card_b = pile[prev_index];
+        for &card_b in &pile[..card_index] {
             if card_a_rank > deck::card_rank(card_b) && card_a_suit == deck::card_suit(card_b) {
                 // Card A should be moved away to unlock card B.
                 return true;
@@ -310,9 +485,9 @@ impl Game {
         key
     }
 
-    pub fn get_base(&self, card: u8) -> Option<usize> {
-        let s = deck::card_suit(card);
-        let r = deck::card_rank(card);
+    pub fn get_base(&self, card: Card) -> Option<usize> {
+        let s = card.suit().index();
+        let r = card.rank().index();
 
         ((BASE_START + s)..BASE_END)
             .step_by(deck::SUIT_NUM)
@@ -320,7 +495,7 @@ impl Game {
     }
 
     pub fn get_empty_cell(&self) -> Option<usize> {
-        cell_range().find(|&i| self.desk[i].is_empty())
+        self.open_cell_range().find(|&i| self.desk[i].is_empty())
     }
 
     pub fn get_empty_pile(&self) -> Option<usize> {
@@ -328,7 +503,9 @@ impl Game {
     }
 
     pub fn get_empty_spot(&self) -> Option<usize> {
-        play_range().find(|&i| self.desk[i].is_empty())
+        self.open_cell_range()
+            .chain(pile_range())
+            .find(|&i| self.desk[i].is_empty())
     }
 
     pub fn has_move_to_cell(&self) -> bool {
@@ -347,16 +524,16 @@ impl Game {
         SingleMoveConsumer::once(|c| self.get_moves_to_tableau(c))
     }
 
-    pub fn card_at(&self, spot: usize) -> Option<&u8> {
-        self.desk[spot].last()
+    pub fn card_at(&self, spot: usize) -> Option<Card> {
+        self.desk[spot].last().map(|&card| Card::from_u8(card))
     }
 
     fn try_move(&self, giver: usize, taker: usize, consumer: &mut impl Consumer<Move>) -> bool {
-        !self.is_move_forward(giver, taker) || consumer.accept(Move::new(giver, taker))
+        !self.is_move_forward(giver, taker) || consumer.accept(Move::at(giver, taker))
     }
 
     fn try_move_to_base(&self, giver: usize, consumer: &mut impl Consumer<Move>) -> bool {
-        if let Some(&card) = self.card_at(giver) {
+        if let Some(card) = self.card_at(giver) {
             if let Some(taker) = self.get_base(card) {
                 return self.try_move(giver, taker, consumer);
             }
@@ -376,7 +553,7 @@ impl Game {
     pub fn get_moves_to_cell(&self, consumer: &mut impl Consumer<Move>) {
         if let Some(taker) = self.get_empty_cell() {
             for giver in pile_range() {
-                if self.desk[giver].len() > 0 && !self.try_move(giver, taker, consumer) {
+                if !self.desk[giver].is_empty() && !self.try_move(giver, taker, consumer) {
                     break;
                 }
             }
@@ -395,7 +572,7 @@ impl Game {
 
             // 2. Test cells:
             for giver in cell_range() {
-                if self.desk[giver].len() > 0 && !self.try_move(giver, taker, consumer) {
+                if !self.desk[giver].is_empty() && !self.try_move(giver, taker, consumer) {
                     return;
                 }
             }
@@ -405,9 +582,9 @@ impl Game {
     pub fn get_moves_to_tableau(&self, consumer: &mut impl Consumer<Move>) {
         // 1. Test cells and piles:
         for giver in play_range() {
-            if let Some(&free_card) = self.card_at(giver) {
+            if let Some(free_card) = self.card_at(giver) {
                 for taker in pile_range() {
-                    if let Some(&pile_card) = self.card_at(taker) {
+                    if let Some(pile_card) = self.card_at(taker) {
                         if giver != taker
                             && is_tableau(pile_card, free_card)
                             && !self.try_move(giver, taker, consumer)
@@ -424,10 +601,10 @@ impl Game {
         // are greater than opposite color bases minimal ranks.
         let ranks = self.base_min_ranks();
         for giver in base_range() {
-            if let Some(&free_card) = self.card_at(giver) {
+            if let Some(free_card) = self.card_at(giver) {
                 if !ranks.ge(free_card) {
                     for taker in pile_range() {
-                        if let Some(&pile_card) = self.card_at(taker) {
+                        if let Some(pile_card) = self.card_at(taker) {
                             if is_tableau(pile_card, free_card)
                                 && !self.try_move(giver, taker, consumer)
                             {
@@ -440,6 +617,76 @@ impl Game {
         }
     }
 
+    /// Returns the move unless it takes back the last one.
+    fn forward_move(&self, giver: usize, taker: usize) -> Option<Move> {
+        Some(Move::at(giver, taker)).filter(|_| self.is_move_forward(giver, taker))
+    }
+
+    /// Iterates over the moves of the top cards of cells and piles to bases.
+    pub fn moves_to_base(&self) -> impl Iterator<Item = Move> + '_ {
+        play_range().filter_map(move |giver| {
+            let taker = self.get_base(self.card_at(giver)?)?;
+            self.forward_move(giver, taker)
+        })
+    }
+
+    /// Iterates over the moves of the top cards of piles to the first empty cell.
+    pub fn moves_to_cell(&self) -> impl Iterator<Item = Move> + '_ {
+        self.get_empty_cell().into_iter().flat_map(move |taker| {
+            pile_range()
+                .filter(move |&giver| !self.desk[giver].is_empty())
+                .filter_map(move |giver| self.forward_move(giver, taker))
+        })
+    }
+
+    /// Iterates over the moves to the first empty pile: from piles of more than one card, then from cells.
+    pub fn moves_to_pile(&self) -> impl Iterator<Item = Move> + '_ {
+        self.get_empty_pile().into_iter().flat_map(move |taker| {
+            // We don't want to move the last card from one pile to another.
+            let piles = pile_range().filter(move |&giver| self.desk[giver].len() > 1);
+            let cells = cell_range().filter(move |&giver| !self.desk[giver].is_empty());
+            piles
+                .chain(cells)
+                .filter_map(move |giver| self.forward_move(giver, taker))
+        })
+    }
+
+    /// Iterates over the moves building tableaux: from cells and piles, then from bases.
+    pub fn moves_to_tableau(&self) -> impl Iterator<Item = Move> + '_ {
+        let onto_piles = move |giver: usize, free_card: Card| {
+            pile_range().filter_map(move |taker| {
+                let pile_card = self.card_at(taker)?;
+                if giver != taker && is_tableau(pile_card, free_card) {
+                    self.forward_move(giver, taker)
+                } else {
+                    None
+                }
+            })
+        };
+        let from_play = play_range()
+            .filter_map(move |giver| self.card_at(giver).map(|card| (giver, card)))
+            .flat_map(move |(giver, card)| onto_piles(giver, card));
+
+        // We can take cards from bases to form a tableau only if their ranks
+        // are greater than opposite color bases minimal ranks.
+        let ranks = self.base_min_ranks();
+        let from_bases = base_range()
+            .filter_map(move |giver| self.card_at(giver).map(|card| (giver, card)))
+            .filter(move |&(_, card)| !ranks.ge(card))
+            .flat_map(move |(giver, card)| onto_piles(giver, card));
+
+        from_play.chain(from_bases)
+    }
+
+    /// Iterates over all moves lazily, in the order of [`Game::get_moves`].
+    /// The consumer versions are faster in tight loops.
+    pub fn moves(&self) -> impl Iterator<Item = Move> + '_ {
+        self.moves_to_base()
+            .chain(self.moves_to_tableau())
+            .chain(self.moves_to_cell())
+            .chain(self.moves_to_pile())
+    }
+
     pub fn get_moves(&self, consumer: &mut impl Consumer<Move>) {
         self.get_moves_to_base(consumer);
         self.get_moves_to_tableau(consumer);
@@ -453,6 +700,12 @@ impl Game {
         consumer.into_vec()
     }
 
+    /// Returns the moves of the position in the order, e.g. the best resulting positions first.
+    /// Random orders are seeded anew on each call, use a [`MoveSorter`] to go on with the sequence.
+    pub fn ordered_moves(&self, order: MoveOrder) -> impl ExactSizeIterator<Item = Move> {
+        MoveSorter::new(order).moves(self)
+    }
+
     pub fn has_next_move(&self) -> bool {
         self.has_move_to_cell()
             || self.has_move_to_pile()
@@ -758,4 +1011,190 @@ mod tests {
         let key_a_2 = game_a.get_invariant();
         assert_eq!(key_a_0, key_a_2);
     }
+
+    #[test]
+    fn typed_moves() {
+        let mv = Move::new(Spot::Cascade(7), Spot::Cell(0)).expect("Should be valid!");
+        assert_eq!(Spot::Cascade(7), mv.giver());
+        assert_eq!(Spot::Cell(0), mv.taker());
+        assert!(Move::new(Spot::Cascade(8), Spot::Cell(0)).is_none());
+        assert!(Move::new(Spot::Cascade(0), Spot::Foundation(4)).is_none());
+        assert!(Move::new(Spot::Cell(1), Spot::Cell(1)).is_none());
+
+        let mut game = Game::new();
+        game.deal(&deck::deal(173205951));
+        game.apply(&mv);
+        assert_eq!(Some(&mv), game.path().last());
+        assert!(game.card_at(CELL_START).is_some());
+        assert_eq!(game.card_at(CELL_START), game.pile(Spot::Cell(0)).last());
+        assert_eq!(1, game.pile(Spot::Cell(0)).count());
+
+        assert_eq!(0, game.base_len(Suit::Spades));
+        game.apply(&Move::new(Spot::Cascade(4), Spot::Foundation(0)).expect("Should be valid!"));
+        assert_eq!(1, game.base_len(Suit::Spades));
+        assert_eq!(0, game.base_len(Suit::Hearts));
+    }
+
+    #[test]
+    fn hex_paths() {
+        let hex = "4871317c7b737478653d35d53d3e39c8";
+        let path = path_from_hex(hex).expect("Should be parsed!");
+        assert_eq!(hex.len() / 2, path.len());
+        assert_eq!(hex, path_to_hex(&path));
+
+        assert!(path_from_hex("487").is_none());
+        assert!(path_from_hex("48x1").is_none());
+        assert_eq!(Some(0), path_from_hex("").map(|path| path.len()));
+    }
+
+    #[test]
+    fn boards() {
+        let cards = deck::deal(173205951);
+        let line = deck::to_string(&cards);
+        assert_eq!(Some(cards.to_vec()), parse_board(&line));
+
+        let piles: Vec<String> = (0..PILE_NUM)
+            .map(|pile| {
+                let pile: Vec<String> = cards
+                    .iter()
+                    .skip(pile)
+                    .step_by(PILE_NUM)
+                    .map(|card| format!("{:#}", card))
+                    .collect();
+                pile.join(" ")
+            })
+            .collect();
+        assert_eq!(Some(cards.to_vec()), parse_board(&piles.join("\n")));
+        assert_eq!(
+            Some(cards.to_vec()),
+            parse_board(&format!("\n{}\n\n", piles.join("\r\n")))
+        );
+
+        assert!(parse_board("").is_none());
+        assert!(parse_board(&piles[1..].join("\n")).is_none());
+        assert!(parse_board(&line.replace("A♠", "2♠")).is_none());
+        assert!(parse_board(&deck::to_string(&cards[1..])).is_none());
+    }
+
+    #[test]
+    fn positions() {
+        let mut game = Game::new();
+        game.deal(&deck::deal(173205951));
+        game.move_cards_auto();
+        game.move_card(PILE_START + 7, PILE_START + 1);
+        game.move_card(PILE_START + 3, PILE_START + 1);
+
+        let mut other = Game::new();
+        other.set_position(&game);
+        for spot in desk_range() {
+            assert!(game.cards_at(spot).eq(other.cards_at(spot)));
+            assert_eq!(game.card_at(spot), other.cards_at(spot).last());
+        }
+        assert_eq!(0, other.path().len());
+        assert_eq!(game.get_invariant(), other.get_invariant());
+        assert_eq!(game.desk(), other.desk());
+
+        other.move_card(PILE_START + 7, CELL_START);
+        other.rewind();
+        assert_eq!(game.desk(), other.desk());
+    }
+
+    #[test]
+    fn cell_limits() {
+        let mut game = Game::new();
+        game.deal(&deck::deal(173205951));
+        assert_eq!(CELL_NUM, game.cell_num());
+
+        game.set_cell_num(1);
+        assert_eq!(1, game.count_empty_cells());
+        assert_eq!(Some(CELL_START), game.get_empty_cell());
+
+        game.move_card(PILE_START + 7, CELL_START);
+        assert_eq!(0, game.count_empty_cells());
+        assert_eq!(None, game.get_empty_cell());
+        assert!(!game.has_move_to_cell());
+        assert_eq!(None, game.get_empty_spot());
+
+        game.set_cell_num(0);
+        game.rewind();
+        assert_eq!(None, game.get_empty_cell());
+        assert!(!game.has_move_to_cell());
+
+        game.set_cell_num(CELL_NUM + 1);
+        assert_eq!(CELL_NUM, game.cell_num());
+        assert_eq!(CELL_NUM, game.count_empty_cells());
+    }
+
+    #[test]
+    fn valid_moves() {
+        let mut game = Game::new();
+        game.deal(&deck::deal(173205951));
+
+        for mv in game.get_all_moves() {
+            assert!(game.is_valid_move(mv.giver().index(), mv.taker().index()));
+        }
+        assert!(!game.is_valid_move(CELL_START, PILE_START));
+        assert!(!game.is_valid_move(PILE_START, PILE_START));
+        assert!(!game.is_valid_move(PILE_START, BASE_START));
+        assert!(game.is_valid_move(PILE_START + 7, CELL_START));
+
+        game.move_card(PILE_START + 7, CELL_START);
+        assert!(!game.is_valid_move(PILE_START, CELL_START));
+        let card = game.card_at(CELL_START).expect("Cell should be filled");
+        for base in base_range() {
+            assert_eq!(
+                game.get_base(card) == Some(base),
+                game.is_valid_move(CELL_START, base)
+            );
+        }
+
+        game.set_cell_num(1);
+        assert!(!game.is_valid_move(PILE_START, CELL_START + 1));
+    }
+
+    #[test]
+    fn move_iterators() {
+        let mut game = Game::new();
+        game.deal(&deck::deal(173205951));
+        let path = path_from_hex("4871317c7b737478653d35d53d3e39c8606c656a60e04e46e6461e1f16f6e6213ed35d535f575171f1272f262b2aead35d5e590939c94a083a395c56060a4204020205050beb6b1b1a1e12e21e1b17152b186869d9f9e96a6b2a2b6a6b2a2b4a38c818787958595a49686b28").expect("Should be parsed!");
+
+        // The iterators yield the moves of the consumer versions, in the same order.
+        fn collected(get: impl Fn(&mut MoveConsumer)) -> Vec<Move> {
+            let mut consumer = MoveConsumer::new();
+            get(&mut consumer);
+            consumer.into_vec()
+        }
+        for mv in &path {
+            let moves: Vec<Move> = game.moves().collect();
+            assert_eq!(game.get_all_moves(), moves);
+            assert_eq!(
+                collected(|c| game.get_moves_to_base(c)),
+                game.moves_to_base().collect::<Vec<_>>()
+            );
+            assert_eq!(
+                collected(|c| game.get_moves_to_tableau(c)),
+                game.moves_to_tableau().collect::<Vec<_>>()
+            );
+            assert_eq!(
+                collected(|c| game.get_moves_to_cell(c)),
+                game.moves_to_cell().collect::<Vec<_>>()
+            );
+            assert_eq!(
+                collected(|c| game.get_moves_to_pile(c)),
+                game.moves_to_pile().collect::<Vec<_>>()
+            );
+            game.apply(mv);
+        }
+        assert!(game.is_done());
+        assert_eq!(0, game.moves().count());
+
+        // Closures are consumers: this one stops after the first tableau move.
+        game.rewind();
+        let mut first = Vec::new();
+        game.get_moves_to_tableau(&mut |mv| {
+            first.push(mv);
+            false
+        });
+        assert_eq!(game.moves_to_tableau().take(1).collect::<Vec<_>>(), first);
+    }
 }
@@ -0,0 +1,259 @@
+//! # Deal generator
+//! Samples deals and keeps the ones matching all predicates, e.g. deals solvable with 2 cells
+//! which need at least 90 moves.
+
+use crate::deck::{self, Card, Rank};
+use crate::freecell::basis::{pile_range, CELL_NUM};
+use crate::freecell::game::Game;
+use crate::freecell::solver::{Limits, Solver};
+use crate::util::Random;
+
+/// A generated deal: either a numbered one or a random permutation of the deck.
+#[derive(Clone, Debug)]
+pub struct Candidate {
+    /// The deal number, [`None`] for random permutations.
+    pub seed: Option<u64>,
+    pub cards: [Card; deck::CARD_NUM],
+}
+
+impl Candidate {
+    /// Returns the game dealt with all cells available.
+    pub fn game(&self) -> Game {
+        let mut game = Game::new();
+        game.deal(&self.cards);
+        game
+    }
+}
+
+/// Where deals are taken from.
+#[derive(Clone, Debug)]
+pub enum DealSource {
+    /// Consecutive deal numbers starting with the given one.
+    Seeds(u64),
+    /// Deal numbers drawn at random, below the given bound.
+    SampledSeeds(Random, u64),
+    /// Random permutations of the deck.
+    Permutations(Random),
+}
+
+/// A deal property.
+pub type Predicate = Box<dyn Fn(&Game) -> bool>;
+
+/// An endless iterator over the deals matching all predicates,
+/// unless the number of tries is limited.
+pub struct Generator {
+    source: DealSource,
+    predicates: Vec<Predicate>,
+    tries_max: Option<usize>,
+    tries: usize,
+}
+
+impl Generator {
+    pub fn new(source: DealSource) -> Self {
+        Self {
+            source,
+            predicates: Vec::new(),
+            tries_max: None,
+            tries: 0,
+        }
+    }
+
+    /// Generates consecutive deals starting with the given number.
+    pub fn seeds(start: u64) -> Self {
+        Self::new(DealSource::Seeds(start))
+    }
+
+    /// Generates random deal numbers below `bound`, reproducible with the same RNG seed.
+    pub fn sampled_seeds(rng_seed: u64, bound: u64) -> Self {
+        Self::new(DealSource::SampledSeeds(
+            Random::new(rng_seed),
+            bound.max(1),
+        ))
+    }
+
+    /// Generates random permutations of the deck, reproducible with the same RNG seed.
+    pub fn permutations(rng_seed: u64) -> Self {
+        Self::new(DealSource::Permutations(Random::new(rng_seed)))
+    }
+
+    /// Adds a predicate all generated deals should match.
+    pub fn filter(mut self, predicate: impl Fn(&Game) -> bool + 'static) -> Self {
+        self.predicates.push(Box::new(predicate));
+        self
+    }
+
+    /// Limits the number of deals to be tested.
+    pub fn tries(mut self, tries_max: usize) -> Self {
+        self.tries_max = Some(tries_max);
+        self
+    }
+
+    /// Returns the number of deals tested so far.
+    pub fn tried(&self) -> usize {
+        self.tries
+    }
+
+    fn sample(&mut self) -> Candidate {
+        match &mut self.source {
+            DealSource::Seeds(next) => {
+                let seed = *next;
+                *next = next.wrapping_add(1);
+                Candidate {
+                    seed: Some(seed),
+                    cards: deck::deal(seed),
+                }
+            }
+            DealSource::SampledSeeds(random, bound) => {
+                let seed = random.below(*bound);
+                Candidate {
+                    seed: Some(seed),
+                    cards: deck::deal(seed),
+                }
+            }
+            DealSource::Permutations(random) => {
+                let mut cards = deck::new();
+                random.shuffle(&mut cards);
+                Candidate { seed: None, cards }
+            }
+        }
+    }
+}
+
+impl Iterator for Generator {
+    type Item = Candidate;
+
+    fn next(&mut self) -> Option<Candidate> {
+        while self
+            .tries_max
+            .is_none_or(|tries_max| self.tries < tries_max)
+        {
+            self.tries += 1;
+            let candidate = self.sample();
+            let game = candidate.game();
+            if self.predicates.iter().all(|predicate| predicate(&game)) {
+                return Some(candidate);
+            }
+        }
+        None
+    }
+}
+
+/// Returns a solver starting from the game position with `cells` available.
+fn solver_from(game: &Game, cells: usize) -> Solver {
+    let mut solver = Solver::new();
+    solver.set_cell_num(cells.min(CELL_NUM));
+    solver.set_auto_play(game.auto_play());
+    solver.start_from(game);
+    solver
+}
+
+/// Returns the shortest solution length found within the limits with `cells` available.
+pub fn solution_len(game: &Game, cells: usize, limits: &Limits) -> Option<usize> {
+    solver_from(game, cells).solve(limits).map(Vec::len)
+}
+
+/// Deals solvable with `cells` available, within the limits.
+pub fn solvable_with(cells: usize, limits: Limits) -> impl Fn(&Game) -> bool {
+    move |game| solution_len(game, cells, &limits).is_some()
+}
+
+/// Deals whose shortest solution found within the limits has at least `len` moves.
+/// The first solution found only bounds the length from above, so the search goes on for shorter ones
+/// whatever `limits.any` says, until one with less than `len` moves is found. Unsolved deals don't match.
+pub fn solution_len_at_least(len: usize, limits: Limits) -> impl Fn(&Game) -> bool {
+    let limits = Limits {
+        any: false,
+        ..limits
+    };
+    move |game| {
+        solver_from(game, CELL_NUM)
+            .solve_anytime(&limits, |_, path| path.len() >= len)
+            .is_some_and(|path| path.len() >= len)
+    }
+}
+
+/// Deals with at least `locks` cards lying on a lower card of the same suit, see [`Game::count_locks`].
+pub fn locks_at_least(locks: usize) -> impl Fn(&Game) -> bool {
+    move |game| game.count_locks() >= locks
+}
+
+/// Deals without aces in the bottom row, i.e. the exposed cards of the piles.
+pub fn no_aces_in_bottom_row() -> impl Fn(&Game) -> bool {
+    |game| {
+        pile_range()
+            .filter_map(|pile| game.card_at(pile))
+            .all(|card| card.rank() != Rank::Ace)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quick() -> Limits {
+        Limits {
+            any: true,
+            done_max: 100000,
+            ..Limits::default()
+        }
+    }
+
+    #[test]
+    fn sources() {
+        let seeds: Vec<_> = Generator::seeds(10).take(3).map(|deal| deal.seed).collect();
+        assert_eq!(vec![Some(10), Some(11), Some(12)], seeds);
+
+        let a: Vec<_> = Generator::sampled_seeds(7, 1000)
+            .take(5)
+            .map(|deal| deal.seed)
+            .collect();
+        let b: Vec<_> = Generator::sampled_seeds(7, 1000)
+            .take(5)
+            .map(|deal| deal.seed)
+            .collect();
+        assert_eq!(a, b);
+        assert!(a.iter().all(|seed| seed.is_some_and(|seed| seed < 1000)));
+
+        let a = Generator::permutations(7)
+            .next()
+            .expect("Should be generated!");
+        let b = Generator::permutations(7)
+            .next()
+            .expect("Should be generated!");
+        assert_eq!(None, a.seed);
+        assert_eq!(a.cards, b.cards);
+        assert_ne!(a.cards, deck::new());
+    }
+
+    #[test]
+    fn predicates() {
+        let mut generator = Generator::seeds(1)
+            .filter(no_aces_in_bottom_row())
+            .tries(20);
+        for deal in generator.by_ref() {
+            let game = deal.game();
+            assert!(pile_range()
+                .all(|pile| game.card_at(pile).expect("Should be dealt!").rank() != Rank::Ace));
+        }
+        assert_eq!(20, generator.tried());
+
+        let impossible = Generator::seeds(1).filter(|_| false).tries(10);
+        assert_eq!(0, impossible.count());
+
+        let deal = Generator::seeds(1)
+            .filter(solvable_with(2, quick()))
+            .filter(solution_len_at_least(80, quick()))
+            .tries(10)
+            .next()
+            .expect("Should be found!");
+        let game = deal.game();
+        assert!(solution_len(&game, 2, &quick()).is_some());
+        assert!(solution_len(&game, CELL_NUM, &quick()).is_some_and(|len| len >= 80));
+
+        // The first solution of deal #1 has 89 moves, a shorter one is found later.
+        let mut game = Game::new();
+        game.deal(&deck::deal(1));
+        assert_eq!(Some(89), solution_len(&game, CELL_NUM, &quick()));
+        assert!(!solution_len_at_least(89, quick())(&game));
+    }
+}
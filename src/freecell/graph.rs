@@ -0,0 +1,225 @@
+//! # Game tree explorer
+//! Expands the move graph around a position, to see what the solver sees there.
+//! The graph is written in the Graphviz DOT language, e.g. `dot -Tsvg graph.dot > graph.svg`.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use crate::deck::Card;
+use crate::freecell::game::{Game, Move, Path};
+use crate::freecell::invariant::Key64;
+use crate::freecell::solver::game_priority;
+
+/// A position of the graph.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct GraphNode {
+    /// The number of moves made by the player to reach the position first.
+    pub depth: usize,
+    /// The path from the root position, including automatic moves to bases.
+    pub path: Path,
+    /// See [`Game::estimate_path_len`].
+    pub estimate: usize,
+    /// See [`game_priority`].
+    pub priority: usize,
+    /// See [`Game::count_locks`].
+    pub locks: usize,
+    pub done: bool,
+    /// The moves from the position have been expanded.
+    pub expanded: bool,
+    /// The position lies on the given solution path.
+    pub on_solution: bool,
+}
+
+/// A player move between two positions of the graph.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct GraphEdge {
+    pub from: usize,
+    pub to: usize,
+    #[cfg_attr(feature = "serde", serde(rename = "move"))]
+    pub mv: Move,
+    pub card: Card,
+    /// The number of automatic moves to bases following the player move.
+    pub auto: usize,
+    pub on_solution: bool,
+}
+
+/// The move graph around a position. Positions reached by different lines are merged.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Graph {
+    /// The positions in the breadth-first order, starting with the root.
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<GraphEdge>,
+}
+
+impl Graph {
+    /// Expands the moves from the position up to `depth` player moves deep.
+    /// The root is the position after the automatic moves to bases, like the solver starts with.
+    /// The `solution` path, relative to the position, marks its nodes and edges.
+    pub fn explore(game: &Game, depth: usize, solution: Option<&[Move]>) -> Self {
+        let mut game = game.clone();
+        let origin = game.path().len();
+        game.move_cards_auto();
+
+        let on_solution =
+            |path: &[Move]| solution.is_some_and(|solution| solution.starts_with(path));
+        let node = |game: &Game, depth: usize| {
+            let path = game.path()[origin..].to_vec();
+            GraphNode {
+                depth,
+                estimate: game.estimate_path_len(),
+                priority: game_priority(game),
+                locks: game.count_locks(),
+                done: game.is_done(),
+                expanded: false,
+                on_solution: on_solution(&path),
+                path,
+            }
+        };
+
+        let mut graph = Graph::default();
+        let mut known: HashMap<Key64, usize> = HashMap::new();
+        known.insert(game.get_invariant(), 0);
+        graph.nodes.push(node(&game, 0));
+
+        let mut next = 0;
+        while next < graph.nodes.len() {
+            let from = next;
+            next += 1;
+            if graph.nodes[from].depth >= depth || graph.nodes[from].done {
+                continue;
+            }
+            graph.nodes[from].expanded = true;
+
+            game.backward(origin);
+            game.forward(graph.nodes[from].path.iter());
+            let mark = game.path().len();
+            for mv in game.get_all_moves() {
+                game.backward(mark);
+                let card = game
+                    .card_at(mv.giver().index())
+                    .expect("Giver should have a card");
+                game.apply(&mv);
+                let auto = game.move_cards_auto();
+
+                let key = game.get_invariant();
+                let to = match known.get(&key) {
+                    Some(&to) => to,
+                    None => {
+                        let to = graph.nodes.len();
+                        known.insert(key, to);
+                        graph.nodes.push(node(&game, graph.nodes[from].depth + 1));
+                        to
+                    }
+                };
+                graph.edges.push(GraphEdge {
+                    from,
+                    to,
+                    card,
+                    auto,
+                    on_solution: on_solution(&game.path()[origin..]),
+                    mv,
+                });
+            }
+        }
+        graph
+    }
+
+    /// Writes the graph in the DOT language.
+    /// Nodes show the path estimate, the solver priority and the lock count,
+    /// edges show the hex move and the card moved. The solution is drawn in red.
+    pub fn to_dot(&self) -> String {
+        let mut dot =
+            String::from("digraph freecell {\n    node [shape=box, fontname=monospace];\n");
+        for (id, node) in self.nodes.iter().enumerate() {
+            let mut attrs = vec![format!(
+                "label=\"#{} d{}\\nest {} prio {}\\nlocks {}\"",
+                id, node.depth, node.estimate, node.priority, node.locks
+            )];
+            if node.done {
+                attrs.push("peripheries=2".to_string());
+            } else if !node.expanded {
+                attrs.push("style=dashed".to_string());
+            }
+            if node.on_solution {
+                attrs.push("color=red".to_string());
+            }
+            let _ = writeln!(dot, "    n{} [{}];", id, attrs.join(", "));
+        }
+        for edge in &self.edges {
+            let mut attrs = vec![format!(
+                "label=\"{}{} {}{}\"",
+                edge.mv.giver().to_hex(),
+                edge.mv.taker().to_hex(),
+                edge.card,
+                if edge.auto > 0 {
+                    format!(" +{}", edge.auto)
+                } else {
+                    String::new()
+                }
+            )];
+            if edge.on_solution {
+                attrs.push("color=red, penwidth=2".to_string());
+            }
+            let _ = writeln!(
+                dot,
+                "    n{} -> n{} [{}];",
+                edge.from,
+                edge.to,
+                attrs.join(", ")
+            );
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deck;
+    use crate::freecell::game::path_from_hex;
+
+    #[test]
+    fn explore() {
+        let mut game = Game::new();
+        game.deal(&deck::deal(173205951));
+        let solution = path_from_hex("4871317c").expect("Should be parsed!");
+        let graph = Graph::explore(&game, 2, Some(&solution));
+
+        let root = &graph.nodes[0];
+        game.move_cards_auto();
+        assert_eq!(
+            game.get_all_moves().len(),
+            graph.edges.iter().filter(|edge| edge.from == 0).count()
+        );
+        // The ace is moved to its base automatically.
+        assert_eq!(&solution[..1], &root.path[..]);
+        assert!(root.expanded && root.on_solution);
+        assert!(graph
+            .nodes
+            .iter()
+            .all(|node| node.depth <= 2 && node.expanded == (node.depth < 2)));
+        // Transpositions are merged: some positions are reached by more than one line.
+        assert!(graph.edges.len() >= graph.nodes.len());
+
+        let marked: Vec<String> = graph
+            .edges
+            .iter()
+            .filter(|edge| edge.on_solution)
+            .map(|edge| format!("{}{}", edge.mv.giver().to_hex(), edge.mv.taker().to_hex()))
+            .collect();
+        assert_eq!(vec!["71", "31"], marked);
+        assert_eq!(
+            3,
+            graph.nodes.iter().filter(|node| node.on_solution).count()
+        );
+
+        let dot = graph.to_dot();
+        assert!(dot.starts_with("digraph freecell {"));
+        assert_eq!(graph.edges.len(), dot.matches(" -> ").count());
+        assert_eq!(2, dot.matches("penwidth=2").count());
+    }
+}
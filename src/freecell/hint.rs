@@ -0,0 +1,94 @@
+//! # Hints
+
+use crate::freecell::game::{Game, Move};
+use crate::freecell::solver::{game_priority, Limits, Solver};
+use std::time::Duration;
+
+/// The maximum number of variants to be processed for a hint.
+pub const HINT_DONE_MAX: usize = 100000;
+
+/// A suggested move.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Hint {
+    pub mv: Move,
+    /// [`true`] if the move is the first one of a found solution,
+    /// [`false`] if it is only the heuristic best one.
+    pub winning: bool,
+}
+
+/// Returns the best next move from the current position within the time limit in milliseconds.
+pub fn hint(game: &Game, time_max: u64) -> Option<Hint> {
+    let limits = Limits {
+        done_max: HINT_DONE_MAX,
+        any: true,
+        time_max: Some(Duration::from_millis(time_max)),
+        ..Limits::default()
+    };
+    hint_with(game, &limits)
+}
+
+/// Returns the first move of a solution found from the current position within the limits.
+/// Falls back to the move leading to the best prioritized position if no solution is found.
+pub fn hint_with(game: &Game, limits: &Limits) -> Option<Hint> {
+    if game.is_done() {
+        return None;
+    }
+
+    let mut solver = Solver::new();
+    solver.set_cell_num(game.cell_num());
+    solver.set_auto_play(game.auto_play());
+    solver.start_from(game);
+    if let Some(mv) = solver.solve(limits).and_then(|path| path.first()) {
+        return Some(Hint {
+            mv: mv.clone(),
+            winning: true,
+        });
+    }
+
+    let mut scratch = game.clone();
+    let mark = scratch.path().len();
+    game.get_all_moves()
+        .into_iter()
+        .min_by_key(|mv| {
+            scratch.backward(mark);
+            scratch.apply(mv);
+            scratch.move_cards_auto();
+            (game_priority(&scratch), scratch.estimate_path_len())
+        })
+        .map(|mv| Hint { mv, winning: false })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deck;
+    use crate::freecell::AutoPlay;
+
+    #[test]
+    fn hints() {
+        let mut game = Game::new();
+        game.deal(&deck::deal(1));
+
+        let hint = hint(&game, 10000).expect("Should be found!");
+        assert!(hint.winning);
+        assert!(game.is_valid_move(hint.mv.giver().index(), hint.mv.taker().index()));
+
+        let limits = Limits {
+            done_max: 0,
+            grab_max: 1,
+            any: true,
+            ..Limits::default()
+        };
+        let hint = hint_with(&game, &limits).expect("Should be found!");
+        assert!(!hint.winning);
+        assert!(game.is_valid_move(hint.mv.giver().index(), hint.mv.taker().index()));
+
+        // Without auto-play, the ace on top of pile 5 waits for the player.
+        let mut game = Game::new();
+        game.set_auto_play(AutoPlay::None);
+        game.deal(&deck::deal(173205951));
+        let found = super::hint(&game, 10000).expect("Should be found!");
+        assert!(found.winning);
+        assert!(game.is_valid_move(found.mv.giver().index(), found.mv.taker().index()));
+    }
+}
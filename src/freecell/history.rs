@@ -0,0 +1,315 @@
+//! # Game history
+//! Keeps every line played from a position as a tree, so that taken back moves can be replayed
+//! and alternatives explored from any point.
+
+use std::collections::BTreeMap;
+
+use crate::freecell::game::{Game, Move, Path};
+
+/// Identifies a position in the history tree.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NodeId(usize);
+
+/// The root position, where the history starts.
+pub const ROOT: NodeId = NodeId(0);
+
+struct Node {
+    parent: Option<NodeId>,
+    /// The moves leading from the parent, e.g. a player move followed by automatic moves.
+    moves: Path,
+    /// Alternative continuations, in the order they were played.
+    children: Vec<NodeId>,
+    /// The continuation to be replayed by [`History::redo`]: the last one visited.
+    redo: Option<NodeId>,
+}
+
+/// A game with the tree of all lines played from its starting position.
+pub struct History {
+    game: Game,
+    /// The path length of the root position.
+    root_mark: usize,
+    nodes: Vec<Node>,
+    current: NodeId,
+    bookmarks: BTreeMap<String, NodeId>,
+}
+
+impl History {
+    /// Starts the history from the current position of the game.
+    pub fn new(game: Game) -> Self {
+        Self {
+            root_mark: game.path().len(),
+            game,
+            nodes: vec![Node {
+                parent: None,
+                moves: Path::new(),
+                children: Vec::new(),
+                redo: None,
+            }],
+            current: ROOT,
+            bookmarks: BTreeMap::new(),
+        }
+    }
+
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    pub fn into_game(self) -> Game {
+        self.game
+    }
+
+    pub fn current(&self) -> NodeId {
+        self.current
+    }
+
+    /// Returns the moves from the root position to the current one.
+    pub fn line(&self) -> &[Move] {
+        &self.game.path()[self.root_mark..]
+    }
+
+    pub fn parent(&self, id: NodeId) -> Option<NodeId> {
+        self.nodes[id.0].parent
+    }
+
+    /// Returns the continuations of the position, in the order they were played.
+    pub fn children(&self, id: NodeId) -> &[NodeId] {
+        &self.nodes[id.0].children
+    }
+
+    /// Returns the moves leading to the position from its parent.
+    pub fn moves(&self, id: NodeId) -> &Path {
+        &self.nodes[id.0].moves
+    }
+
+    /// Returns the continuations of the current position.
+    pub fn variations(&self) -> &[NodeId] {
+        self.children(self.current)
+    }
+
+    /// Makes a move as one step if it's valid.
+    pub fn play(&mut self, mv: &Move) -> bool {
+        if !self
+            .game
+            .is_valid_move(mv.giver().index(), mv.taker().index())
+        {
+            return false;
+        }
+        self.game.apply(mv);
+        self.commit();
+        true
+    }
+
+    /// Makes a move if it's valid, followed by the automatic moves to bases, as one step.
+    pub fn play_auto(&mut self, mv: &Move) -> bool {
+        if !self
+            .game
+            .is_valid_move(mv.giver().index(), mv.taker().index())
+        {
+            return false;
+        }
+        self.game.apply(mv);
+        self.game.move_cards_auto();
+        self.commit();
+        true
+    }
+
+    /// Plays the moves as one step if they're all valid.
+    /// Replaying a known continuation follows it instead of adding a new one.
+    pub fn play_path(&mut self, moves: &[Move]) -> bool {
+        let mark = self.game.path().len();
+        for mv in moves {
+            if !self
+                .game
+                .is_valid_move(mv.giver().index(), mv.taker().index())
+            {
+                self.game.backward(mark);
+                return false;
+            }
+            self.game.apply(mv);
+        }
+        self.commit();
+        true
+    }
+
+    /// Records the moves made since the current position as a step to a new or known position.
+    fn commit(&mut self) {
+        let mark = self.mark(self.current);
+        let moves = self.game.path()[mark..].to_vec();
+        if moves.is_empty() {
+            return;
+        }
+        let known = self
+            .children(self.current)
+            .iter()
+            .copied()
+            .find(|&child| self.moves(child) == &moves);
+        let child = known.unwrap_or_else(|| {
+            let child = NodeId(self.nodes.len());
+            self.nodes.push(Node {
+                parent: Some(self.current),
+                moves,
+                children: Vec::new(),
+                redo: None,
+            });
+            self.nodes[self.current.0].children.push(child);
+            child
+        });
+        self.nodes[self.current.0].redo = Some(child);
+        self.current = child;
+    }
+
+    /// Returns the path length of the position.
+    fn mark(&self, id: NodeId) -> usize {
+        self.root_mark
+            + self
+                .ancestors(id)
+                .map(|id| self.moves(id).len())
+                .sum::<usize>()
+    }
+
+    /// Iterates from the position up to the root.
+    fn ancestors(&self, id: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        std::iter::successors(Some(id), move |&id| self.parent(id))
+    }
+
+    /// Takes back the last step. Returns [`false`] at the root.
+    pub fn undo(&mut self) -> bool {
+        match self.parent(self.current) {
+            Some(parent) => {
+                self.game.backward(self.mark(parent));
+                self.current = parent;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Replays the last visited continuation. Returns [`false`] if there is none.
+    pub fn redo(&mut self) -> bool {
+        match self.nodes[self.current.0].redo {
+            Some(child) => {
+                self.game.forward(self.nodes[child.0].moves.iter());
+                self.current = child;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Goes to any position of the tree. Redo follows the line to it afterwards.
+    pub fn goto(&mut self, id: NodeId) -> bool {
+        if id.0 >= self.nodes.len() {
+            return false;
+        }
+        let mut line: Vec<NodeId> = self.ancestors(id).collect();
+        line.reverse();
+        for pair in line.windows(2) {
+            self.nodes[pair[0].0].redo = Some(pair[1]);
+        }
+
+        self.game.backward(self.root_mark);
+        for &node in &line[1..] {
+            self.game.forward(self.nodes[node.0].moves.iter());
+        }
+        self.current = id;
+        true
+    }
+
+    /// Names the current position. An existing bookmark of the same name is moved.
+    pub fn bookmark(&mut self, name: &str) {
+        self.bookmarks.insert(name.to_string(), self.current);
+    }
+
+    pub fn remove_bookmark(&mut self, name: &str) -> bool {
+        self.bookmarks.remove(name).is_some()
+    }
+
+    /// Goes to the named position. Returns [`false`] if there is no such bookmark.
+    pub fn goto_bookmark(&mut self, name: &str) -> bool {
+        match self.bookmarks.get(name) {
+            Some(&id) => self.goto(id),
+            None => false,
+        }
+    }
+
+    /// Returns the bookmarks ordered by name.
+    pub fn bookmarks(&self) -> impl Iterator<Item = (&str, NodeId)> {
+        self.bookmarks.iter().map(|(name, &id)| (name.as_str(), id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deck;
+    use crate::freecell::game::path_from_hex;
+
+    fn moves(hex: &str) -> Path {
+        path_from_hex(hex).expect("Should be parsed!")
+    }
+
+    #[test]
+    fn undo_redo() {
+        let mut game = Game::new();
+        game.deal(&deck::deal(173205951));
+        let mut history = History::new(game);
+
+        for mv in &moves("4871317c") {
+            assert!(history.play(mv));
+        }
+        assert!(!history.play(&moves("c7")[0]));
+        assert_eq!(&moves("4871317c")[..], history.line());
+
+        assert!(history.undo());
+        assert!(history.undo());
+        assert_eq!(&moves("4871")[..], history.line());
+        assert!(history.redo());
+        assert!(history.redo());
+        assert!(!history.redo());
+        assert_eq!(&moves("4871317c")[..], history.line());
+
+        while history.undo() {}
+        assert_eq!(ROOT, history.current());
+        assert!(history.line().is_empty());
+        assert!(history.redo());
+        assert_eq!(&moves("48")[..], history.line());
+    }
+
+    #[test]
+    fn variations() {
+        let mut game = Game::new();
+        game.deal(&deck::deal(173205951));
+        let mut history = History::new(game);
+
+        assert!(history.play_path(&moves("4871")));
+        history.bookmark("before cell fill");
+        assert!(history.play_path(&moves("317c")));
+        let main = history.current();
+
+        assert!(history.goto_bookmark("before cell fill"));
+        assert!(history.play_path(&moves("3c")));
+        let side = history.current();
+        assert_ne!(main, side);
+
+        assert!(history.undo());
+        assert_eq!(2, history.variations().len());
+        // Redo follows the last visited line.
+        assert!(history.redo());
+        assert_eq!(side, history.current());
+
+        // Replaying a known line doesn't add a variation.
+        assert!(history.goto_bookmark("before cell fill"));
+        assert!(history.play_path(&moves("317c")));
+        assert_eq!(main, history.current());
+
+        assert!(history.goto(side));
+        assert_eq!(&moves("48713c")[..], history.line());
+        assert!(history.goto(ROOT));
+        assert!(history.redo() && history.redo());
+        assert_eq!(side, history.current());
+
+        assert!(history.remove_bookmark("before cell fill"));
+        assert!(!history.goto_bookmark("before cell fill"));
+        assert_eq!(0, history.bookmarks().count());
+    }
+}
@@ -10,6 +10,12 @@ pub struct Key64 {
     data: [u8; KEY_SIZE],
 }
 
+impl Default for Key64 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Key64 {
     pub fn new() -> Self {
         Key64 {
@@ -20,6 +26,10 @@ impl Key64 {
     pub fn put(&mut self, index: usize, value: u8) {
         self.data[index] = value;
     }
+
+    pub fn as_bytes(&self) -> &[u8; KEY_SIZE] {
+        &self.data
+    }
 }
 
 #[cfg(test)]
@@ -13,12 +13,36 @@
 //! - Any top card may be moved to build on a tableau, or moved to an empty cell, an empty cascade, or its foundation.
 //! ### Victory
 //! - The game is won after all cards are moved to their foundation piles.
+mod analysis;
+mod autoplay;
 mod basis;
+mod beam;
 mod game;
+mod generator;
+mod graph;
+mod hint;
+mod history;
 mod invariant;
+mod ordering;
+mod portfolio;
 mod solver;
+mod stats;
+mod status;
+mod trace;
 
+pub use analysis::*;
+pub use autoplay::*;
 pub use basis::*;
+pub use beam::*;
 pub use game::*;
+pub use generator::*;
+pub use graph::*;
+pub use hint::*;
+pub use history::*;
 pub use invariant::*;
+pub use ordering::*;
+pub use portfolio::*;
 pub use solver::*;
+pub use stats::*;
+pub use status::*;
+pub use trace::*;
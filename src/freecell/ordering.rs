@@ -0,0 +1,236 @@
+//! # Move ordering
+//! [`Game::get_moves`] emits the moves to bases first, then to tableaux, cells and piles, each in spot order.
+//! The search expands moves in that order, which biases it towards the first spots.
+//! [`MoveOrder`] sorts the moves differently.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::deck;
+use crate::freecell::basis::Spot;
+use crate::freecell::game::{Game, Move, Path};
+use crate::freecell::solver::position_score;
+use crate::util::Random;
+
+/// How the moves of a position are ordered.
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum MoveOrder {
+    /// The order of [`Game::get_moves`].
+    #[default]
+    Standard,
+    /// The best resulting positions by [`position_score`] first, after the automatic moves to bases.
+    Score,
+    /// The moves exposing a card which goes home soon first: the one closest to its base's top card.
+    HomeSoon,
+    /// Shuffled, reproducibly with the same seed.
+    Random(u64),
+}
+
+/// The error returned when a move order can't be parsed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseMoveOrderError(String);
+
+impl fmt::Display for ParseMoveOrderError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid move order: '{}'", self.0)
+    }
+}
+
+impl std::error::Error for ParseMoveOrderError {}
+
+impl fmt::Display for MoveOrder {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MoveOrder::Standard => write!(f, "standard"),
+            MoveOrder::Score => write!(f, "score"),
+            MoveOrder::HomeSoon => write!(f, "home"),
+            MoveOrder::Random(seed) => write!(f, "random:{}", seed),
+        }
+    }
+}
+
+impl FromStr for MoveOrder {
+    type Err = ParseMoveOrderError;
+
+    /// Parses "standard", "score", "home" or "random:SEED", ignoring case.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lower = s.to_ascii_lowercase();
+        match lower.split_once(':') {
+            None if lower == "standard" => Some(MoveOrder::Standard),
+            None if lower == "score" => Some(MoveOrder::Score),
+            None if lower == "home" => Some(MoveOrder::HomeSoon),
+            Some(("random", seed)) => seed.parse().map(MoveOrder::Random).ok(),
+            _ => None,
+        }
+        .ok_or_else(|| ParseMoveOrderError(s.to_string()))
+    }
+}
+
+/// Returns the number of cards to be put on the base before the card can go there.
+fn home_distance(game: &Game, card: deck::Card) -> usize {
+    card.rank()
+        .index()
+        .saturating_sub(game.base_len(card.suit()))
+}
+
+/// Rates the card exposed by a move: lower is better. Moves to bases and emptying piles rate best.
+fn exposed_rating(game: &mut Game, mv: &Move) -> usize {
+    if let Spot::Foundation(_) = mv.taker() {
+        return 0;
+    }
+    let mark = game.path().len();
+    game.apply(mv);
+    let rating = match (mv.giver(), game.card_at(mv.giver().index())) {
+        (Spot::Cascade(_), Some(card)) => 1 + home_distance(game, card),
+        (Spot::Cascade(_), None) => 0,
+        _ => 1 + deck::RANK_NUM,
+    };
+    game.backward(mark);
+    rating
+}
+
+/// Sorts moves in a [`MoveOrder`], keeping the random state between calls.
+#[derive(Clone, Default)]
+pub struct MoveSorter {
+    order: MoveOrder,
+    random: Option<Random>,
+    /// The position the moves are tried on, kept to save allocations.
+    scratch: Game,
+}
+
+impl MoveSorter {
+    pub fn new(order: MoveOrder) -> Self {
+        let random = match order {
+            MoveOrder::Random(seed) => Some(Random::new(seed)),
+            _ => None,
+        };
+        Self {
+            order,
+            random,
+            scratch: Game::new(),
+        }
+    }
+
+    pub fn order(&self) -> &MoveOrder {
+        &self.order
+    }
+
+    /// Starts over: the random order repeats from the seed.
+    pub fn reset(&mut self) {
+        if let MoveOrder::Random(seed) = self.order {
+            self.random = Some(Random::new(seed));
+        }
+    }
+
+    /// Returns the scratch game set to the position of the game.
+    fn scratch(&mut self, game: &Game) -> &mut Game {
+        let scratch = &mut self.scratch;
+        scratch.set_cell_num(game.cell_num());
+        scratch.set_auto_play(game.auto_play());
+        scratch.set_position(game);
+        scratch
+    }
+
+    /// Sorts the moves of the game position. Moves on equal terms keep their standard order.
+    pub fn sort(&mut self, game: &Game, moves: &mut Path) {
+        match self.order {
+            MoveOrder::Standard => {}
+            MoveOrder::Score => {
+                let scratch = self.scratch(game);
+                moves.sort_by_cached_key(|mv| {
+                    scratch.apply(mv);
+                    scratch.move_cards_auto();
+                    let score = position_score(scratch);
+                    scratch.backward(0);
+                    score
+                });
+            }
+            MoveOrder::HomeSoon => {
+                let scratch = self.scratch(game);
+                moves.sort_by_cached_key(|mv| exposed_rating(scratch, mv));
+            }
+            MoveOrder::Random(_) => {
+                if let Some(random) = &mut self.random {
+                    random.shuffle(moves);
+                }
+            }
+        }
+    }
+
+    /// Returns the moves of the game position in order.
+    pub fn moves(&mut self, game: &Game) -> impl ExactSizeIterator<Item = Move> {
+        let mut moves = game.get_all_moves();
+        self.sort(game, &mut moves);
+        moves.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn orders() {
+        let mut game = Game::new();
+        game.deal(&deck::deal(173205951));
+        game.move_cards_auto();
+        let standard = game.get_all_moves();
+        assert_eq!(
+            standard,
+            game.ordered_moves(MoveOrder::Standard).collect::<Path>()
+        );
+
+        let same_moves = |moves: Path| {
+            let mut sorted = moves.clone();
+            sorted.sort_by_key(|mv| (mv.giver().index(), mv.taker().index()));
+            let mut expected = standard.clone();
+            expected.sort_by_key(|mv| (mv.giver().index(), mv.taker().index()));
+            sorted == expected
+        };
+
+        let scored: Path = game.ordered_moves(MoveOrder::Score).collect();
+        assert!(same_moves(scored.clone()));
+        let scores: Vec<usize> = scored
+            .iter()
+            .map(|mv| {
+                let mut next = game.clone();
+                next.apply(mv);
+                next.move_cards_auto();
+                position_score(&next)
+            })
+            .collect();
+        assert!(scores.windows(2).all(|pair| pair[0] <= pair[1]));
+
+        let home: Path = game.ordered_moves(MoveOrder::HomeSoon).collect();
+        assert!(same_moves(home.clone()));
+        let mut scratch = game.clone();
+        let ratings: Vec<usize> = home
+            .iter()
+            .map(|mv| exposed_rating(&mut scratch, mv))
+            .collect();
+        assert!(ratings.windows(2).all(|pair| pair[0] <= pair[1]));
+
+        let a: Path = game.ordered_moves(MoveOrder::Random(7)).collect();
+        assert!(same_moves(a.clone()));
+        assert_eq!(
+            a,
+            game.ordered_moves(MoveOrder::Random(7)).collect::<Path>()
+        );
+        let mut sorter = MoveSorter::new(MoveOrder::Random(7));
+        assert_eq!(a, sorter.moves(&game).collect::<Path>());
+        sorter.reset();
+        assert_eq!(a, sorter.moves(&game).collect::<Path>());
+
+        for order in [
+            MoveOrder::Standard,
+            MoveOrder::Score,
+            MoveOrder::HomeSoon,
+            MoveOrder::Random(7),
+        ] {
+            assert_eq!(Ok(order.clone()), order.to_string().parse());
+        }
+        assert!("random".parse::<MoveOrder>().is_err());
+    }
+}
@@ -0,0 +1,318 @@
+//! # Portfolio solving
+//! Runs several [`Solver`] configurations on the same position, either in turns or concurrently.
+//! Deals the default configuration struggles with are often solved quickly with a different move order
+//! or different priority weights.
+
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::freecell::game::{Game, Path};
+use crate::freecell::ordering::MoveOrder;
+use crate::freecell::solver::{Limits, Priority, Solver};
+
+/// A solver configuration.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct Config {
+    /// The maximum number of variants to be processed in one iteration, see [`Limits::grab_max`].
+    pub grab_max: usize,
+    pub priority: Priority,
+    /// See [`Solver::set_move_order`].
+    pub move_order: MoveOrder,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            grab_max: Limits::default().grab_max,
+            priority: Priority::default(),
+            move_order: MoveOrder::Standard,
+        }
+    }
+}
+
+impl Config {
+    /// Returns `count` configurations: the default one first, followed by variations
+    /// of the iteration size, the weight of locks and the move order.
+    pub fn variations(count: usize) -> Vec<Config> {
+        let grab_maxes = [1000, 100, 10000];
+        let locks = [9, 20, 5];
+        (0..count)
+            .map(|i| Config {
+                grab_max: grab_maxes[i % grab_maxes.len()],
+                priority: Priority {
+                    locks: locks[i / grab_maxes.len() % locks.len()],
+                    ..Priority::default()
+                },
+                move_order: match i % 4 {
+                    _ if i == 0 => MoveOrder::Standard,
+                    1 => MoveOrder::Score,
+                    2 => MoveOrder::HomeSoon,
+                    _ => MoveOrder::Random(i as u64),
+                },
+            })
+            .collect()
+    }
+
+    fn solver(&self, game: &Game) -> Solver {
+        let mut solver = Solver::new();
+        solver.set_cell_num(game.cell_num());
+        solver.set_auto_play(game.auto_play());
+        solver.set_priority(self.priority.clone());
+        solver.set_move_order(self.move_order.clone());
+        solver.start_from(game);
+        solver
+    }
+}
+
+impl fmt::Display for Config {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "grab {}, locks weight {}",
+            self.grab_max, self.priority.locks
+        )?;
+        write!(f, ", {} move order", self.move_order)
+    }
+}
+
+/// The solution found by a portfolio.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Win {
+    /// The path relative to the position.
+    pub path: Path,
+    /// The index of the configuration which found it.
+    pub config: usize,
+    /// The number of positions explored by all configurations.
+    pub done: usize,
+}
+
+/// Several solver configurations searching the same position.
+pub struct Portfolio {
+    configs: Vec<Config>,
+    limits: Limits,
+    slice: usize,
+}
+
+impl Portfolio {
+    /// Runs the configurations within the limits, each one on its own.
+    /// With [`Limits::any`], the first solution found wins, otherwise the shortest one does.
+    /// [`Limits::grab_max`] is overridden by the [`Config::grab_max`] of each configuration,
+    /// [`Limits::done_max`] is per configuration and [`Limits::time_max`] is for the whole run.
+    pub fn new(configs: Vec<Config>, limits: Limits) -> Self {
+        Self {
+            configs,
+            limits,
+            slice: 10000,
+        }
+    }
+
+    /// Sets the number of positions each configuration explores per turn when run in turns.
+    pub fn slice(mut self, slice: usize) -> Self {
+        self.slice = slice.max(1);
+        self
+    }
+
+    pub fn configs(&self) -> &[Config] {
+        &self.configs
+    }
+
+    /// Returns the start and the duration of the run, if limited.
+    fn deadline(&self) -> Option<(Instant, Duration)> {
+        // Note: The clock is read only if needed, it's not available on all targets (e.g. wasm32).
+        self.limits.time_max.map(|time| (Instant::now(), time))
+    }
+
+    fn is_late(deadline: Option<(Instant, Duration)>) -> bool {
+        deadline.is_some_and(|(start, time)| start.elapsed() >= time)
+    }
+
+    /// Picks the shortest solution, the one of the first configuration on ties.
+    fn pick(found: impl Iterator<Item = (usize, Option<Path>)>, done: usize) -> Option<Win> {
+        found
+            .filter_map(|(config, path)| path.map(|path| (config, path)))
+            .min_by_key(|(config, path)| (path.len(), *config))
+            .map(|(config, path)| Win { path, config, done })
+    }
+
+    /// Runs the configurations round-robin in the current thread.
+    pub fn run_in_turns(&self, game: &Game) -> Option<Win> {
+        let deadline = self.deadline();
+        let mut solvers: Vec<(Solver, bool)> = self
+            .configs
+            .iter()
+            .map(|config| (config.solver(game), true))
+            .collect();
+        let done = |solvers: &[(Solver, bool)]| {
+            solvers.iter().map(|(solver, _)| solver.done().len()).sum()
+        };
+
+        'turns: while solvers.iter().any(|&(_, active)| active) {
+            for (i, (solver, active)) in solvers.iter_mut().enumerate() {
+                if !*active {
+                    continue;
+                }
+                let turn_max = solver.done().len() + self.slice;
+                loop {
+                    match solver.next(self.limits.path_max, self.configs[i].grab_max, false) {
+                        None => {
+                            *active = false;
+                            break;
+                        }
+                        Some(true) if self.limits.any => {
+                            let path = solver.path().cloned();
+                            return Self::pick(std::iter::once((i, path)), done(&solvers));
+                        }
+                        Some(_) => {}
+                    }
+                    if solver.done().len() > self.limits.done_max {
+                        *active = false;
+                        break;
+                    }
+                    if Self::is_late(deadline) {
+                        break 'turns;
+                    }
+                    if solver.done().len() >= turn_max {
+                        break;
+                    }
+                }
+            }
+        }
+        let done = done(&solvers);
+        Self::pick(
+            solvers
+                .into_iter()
+                .enumerate()
+                .map(|(i, (solver, _))| (i, solver.into_solution().1)),
+            done,
+        )
+    }
+
+    /// Runs each configuration in its own thread.
+    pub fn run_concurrently(&self, game: &Game) -> Option<Win> {
+        let deadline = self.deadline();
+        let stop = AtomicBool::new(false);
+        let first: Mutex<Option<usize>> = Mutex::new(None);
+        let results: Vec<(Option<Path>, usize)> = thread::scope(|scope| {
+            let workers: Vec<_> = self
+                .configs
+                .iter()
+                .enumerate()
+                .map(|(i, config)| {
+                    let (stop, first) = (&stop, &first);
+                    scope.spawn(move || {
+                        let mut solver = config.solver(game);
+                        while !stop.load(Ordering::Relaxed) {
+                            match solver.next(self.limits.path_max, config.grab_max, false) {
+                                None => break,
+                                Some(true) if self.limits.any => {
+                                    let mut first = first.lock().expect("Should be locked!");
+                                    if first.is_none() {
+                                        *first = Some(i);
+                                        stop.store(true, Ordering::Relaxed);
+                                    }
+                                    break;
+                                }
+                                Some(_) => {}
+                            }
+                            if solver.done().len() > self.limits.done_max || Self::is_late(deadline)
+                            {
+                                break;
+                            }
+                        }
+                        let done = solver.done().len();
+                        (solver.into_solution().1, done)
+                    })
+                })
+                .collect();
+            workers
+                .into_iter()
+                .map(|worker| worker.join().expect("Solver should not panic"))
+                .collect()
+        });
+
+        let done = results.iter().map(|(_, done)| done).sum();
+        let mut found = results
+            .into_iter()
+            .map(|(path, _)| path)
+            .enumerate()
+            .collect::<Vec<_>>();
+        match first.into_inner().expect("Should be unlocked!") {
+            Some(winner) => Self::pick(found.drain(winner..=winner), done),
+            None => Self::pick(found.into_iter(), done),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deck;
+
+    #[test]
+    fn portfolio() {
+        let configs = Config::variations(4);
+        assert_eq!(Config::default(), configs[0]);
+        assert_eq!(
+            4,
+            configs
+                .iter()
+                .map(|config| config.move_order.to_string())
+                .collect::<std::collections::HashSet<_>>()
+                .len()
+        );
+
+        let mut game = Game::new();
+        game.deal(&deck::deal(1));
+        let limits = Limits {
+            any: true,
+            done_max: 100000,
+            ..Limits::default()
+        };
+        let portfolio = Portfolio::new(configs, limits.clone()).slice(1000);
+        let mut first = Vec::new();
+        for win in [
+            portfolio.run_in_turns(&game),
+            portfolio.run_concurrently(&game),
+        ] {
+            let win = win.expect("Should be solved!");
+            assert!(win.config < 4 && win.done > 0);
+            let mut solved = game.clone();
+            solved.forward(win.path.iter());
+            assert!(solved.is_done());
+            first.push(win.path.len());
+        }
+
+        // The shortest solution wins if the configurations run to their budget.
+        let portfolio = Portfolio::new(
+            Config::variations(2),
+            Limits {
+                any: false,
+                done_max: 40000,
+                ..limits
+            },
+        );
+        let win = portfolio.run_in_turns(&game).expect("Should be solved!");
+        assert!(win.path.len() < first[0]);
+
+        // The time limit is for the whole run.
+        let time_max = Duration::from_millis(100);
+        let portfolio = Portfolio::new(
+            Config::variations(4),
+            Limits {
+                any: false,
+                time_max: Some(time_max),
+                ..Limits::default()
+            },
+        );
+        for run in [Portfolio::run_in_turns, Portfolio::run_concurrently] {
+            let start = Instant::now();
+            run(&portfolio, &game);
+            assert!(start.elapsed() < 5 * time_max);
+        }
+    }
+}
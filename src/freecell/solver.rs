@@ -1,25 +1,104 @@
 use crate::deck;
+use crate::freecell::autoplay::AutoPlay;
 use crate::freecell::game::{Game, Path};
 use crate::freecell::invariant::Key64;
+use crate::freecell::ordering::{MoveOrder, MoveSorter};
+use crate::freecell::stats::{SolutionFound, SolverStats};
+use crate::freecell::trace::{Outcome, TraceEvent, Tracer, ROOT_ID};
 use crate::util::Grader;
 use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Rates how far the position is from the solution regardless of the path: lower is better.
+pub fn position_score(game: &Game) -> usize {
+    Priority::default().position_score(game)
+}
 
 pub fn game_priority(game: &Game) -> usize {
-    // Solved: 10000. From 1 to 10000.
-    // Solver Stats:
-    // Average path: 93.0184.
-    // Minimum path: 70 at: 293
-    // Maximum path: 121 at: 3676
-
-    let len = game.path().len();
-    if len < 8 {
-        0
-    } else if len > 88 {
-        10 * game.count_unsolved() + 9 * game.count_locks() + len * 8
-    } else {
-        10 * game.count_unsolved() + 9 * game.count_locks() + len * 4
-    }
-    // 10 * game.count_unsolved() + 9 * game.count_locks() + len
+    Priority::default().grade(game)
+}
+
+/// The weights of the bank grade given to positions by [`Solver`]: lower grades are expanded first.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct Priority {
+    /// The weight of the cards not in bases yet.
+    pub unsolved: usize,
+    /// The weight of the locked cards, see [`Game::count_locks`].
+    pub locks: usize,
+    /// The weight of the path length.
+    pub len: usize,
+    /// The weight of the path length over [`Priority::late`] moves.
+    pub late_len: usize,
+    /// The path length where the late weight takes over.
+    pub late: usize,
+    /// Shorter paths all get the first grade.
+    pub early: usize,
+}
+
+impl Default for Priority {
+    fn default() -> Self {
+        // Solved: 10000. From 1 to 10000.
+        // Solver Stats:
+        // Average path: 93.0184.
+        // Minimum path: 70 at: 293
+        // Maximum path: 121 at: 3676
+        Self {
+            unsolved: 10,
+            locks: 9,
+            len: 4,
+            late_len: 8,
+            late: 88,
+            early: 8,
+        }
+    }
+}
+
+impl Priority {
+    pub fn position_score(&self, game: &Game) -> usize {
+        self.unsolved * game.count_unsolved() + self.locks * game.count_locks()
+    }
+
+    pub fn grade(&self, game: &Game) -> usize {
+        let len = game.path().len();
+        if len < self.early {
+            0
+        } else if len > self.late {
+            self.position_score(game) + len * self.late_len
+        } else {
+            self.position_score(game) + len * self.len
+        }
+    }
+}
+
+/// Search limits used by [`Solver::solve`].
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct Limits {
+    /// The upper bound of the solution length (exclusive).
+    pub path_max: usize,
+    /// The maximum number of variants to be processed in one iteration.
+    pub grab_max: usize,
+    /// The maximum number of variants to be processed in total.
+    pub done_max: usize,
+    /// Stop on the first solution found.
+    pub any: bool,
+    /// The maximum time to be spent in total.
+    pub time_max: Option<Duration>,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            path_max: 257,
+            grab_max: 1000,
+            done_max: 10000000,
+            any: false,
+            time_max: None,
+        }
+    }
 }
 
 type Bank = Grader<usize, Path>;
@@ -30,6 +109,10 @@ pub struct Solver {
     done: Done,
     game: Game,
     path: Option<Path>,
+    tracer: Option<Tracer>,
+    stats: SolverStats,
+    priority: Priority,
+    sorter: MoveSorter,
 }
 
 fn clean_bank(bank: &mut Bank, game: &mut Game, path_upper_limit: usize) -> usize {
@@ -40,12 +123,18 @@ fn clean_bank(bank: &mut Bank, game: &mut Game, path_upper_limit: usize) -> usiz
             game.set_path(path.iter());
             game.estimate_path_len() < path_upper_limit
         });
-        row.len() > 0
+        !row.is_empty()
     });
 
     old_len - bank.len()
 }
 
+impl Default for Solver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Solver {
     pub fn new() -> Self {
         Self {
@@ -53,6 +142,10 @@ impl Solver {
             done: HashMap::new(),
             game: Game::new(),
             path: None,
+            tracer: None,
+            stats: SolverStats::default(),
+            priority: Priority::default(),
+            sorter: MoveSorter::default(),
         }
     }
 
@@ -61,18 +154,77 @@ impl Solver {
         self.bank.clear();
         self.done.clear();
         self.path = None;
+        self.stats = SolverStats::new(self.stats.timings.is_some());
+    }
+
+    /// Limits the number of cells available for play, blocking the remaining ones.
+    pub fn set_cell_num(&mut self, cell_num: usize) {
+        self.game.set_cell_num(cell_num);
+    }
+
+    /// Sets which cards are moved to bases automatically after each move, see [`AutoPlay`].
+    pub fn set_auto_play(&mut self, auto_play: AutoPlay) {
+        self.game.set_auto_play(auto_play);
+    }
+
+    /// Sets the recorder of the positions generated by the search, see [`Tracer`].
+    /// It's to be set before the search starts, to know the starting position.
+    pub fn set_tracer(&mut self, tracer: Option<Tracer>) {
+        self.tracer = tracer;
+    }
+
+    pub fn take_tracer(&mut self) -> Option<Tracer> {
+        self.tracer.take()
+    }
+
+    /// Sets the weights of the bank grades.
+    pub fn set_priority(&mut self, priority: Priority) {
+        self.priority = priority;
+    }
+
+    /// Sets the order the moves of each expanded position are tried in.
+    /// Random orders repeat from the seed when the search starts.
+    pub fn set_move_order(&mut self, order: MoveOrder) {
+        self.sorter = MoveSorter::new(order);
+    }
+
+    /// Measures the time spent in the search stages, see [`SolverStats::timings`].
+    /// Reads the clock, which isn't available on all targets (e.g. wasm32).
+    pub fn set_timed(&mut self, timed: bool) {
+        if timed != self.stats.timings.is_some() {
+            self.stats.timings = SolverStats::new(timed).timings;
+        }
     }
 
     pub fn deal(&mut self, seed: u64) {
         self.clear();
-
         self.game.deal(&deck::deal(seed));
+        self.start();
+    }
+
+    /// Starts the search from any position, including cards already in cells and bases.
+    /// Found paths are relative to that position.
+    pub fn start_from(&mut self, game: &Game) {
+        self.clear();
+        self.game.set_position(game);
+        self.start();
+    }
+
+    fn start(&mut self) {
+        self.sorter.reset();
         self.game.move_cards_auto();
 
-        self.bank.add(0, self.game.path().clone());
+        if self.game.is_done() {
+            self.path = Some(self.game.path().clone());
+        } else {
+            self.bank.add(0, self.game.path().clone());
+        }
 
-        self.done
-            .insert(self.game.get_invariant(), self.game.path().len());
+        let key = self.game.get_invariant();
+        self.done.insert(key, self.game.path().len());
+        if let Some(tracer) = &mut self.tracer {
+            tracer.start(key);
+        }
 
         self.game.rewind();
     }
@@ -89,6 +241,10 @@ impl Solver {
         &self.game
     }
 
+    pub fn stats(&self) -> &SolverStats {
+        &self.stats
+    }
+
     pub fn path(&self) -> Option<&Path> {
         self.path.as_ref()
     }
@@ -97,7 +253,66 @@ impl Solver {
         (self.game, self.path)
     }
 
+    /// Runs the search until the bank is exhausted or the limits are reached.
+    /// Returns the shortest path found.
+    pub fn solve(&mut self, limits: &Limits) -> Option<&Path> {
+        self.solve_anytime(limits, |_, _| true)
+    }
+
+    /// Runs the search like [`Solver::solve`], reporting each solution found as soon as it's found.
+    /// Every solution is shorter than the previous one. The search stops when the callback returns [`false`].
+    /// The solutions are timed if the solver is, see [`Solver::set_timed`].
+    pub fn solve_anytime(
+        &mut self,
+        limits: &Limits,
+        mut on_solution: impl FnMut(&SolutionFound, &Path) -> bool,
+    ) -> Option<&Path> {
+        // Note: The clock is read only if needed, it's not available on all targets (e.g. wasm32).
+        let deadline = limits.time_max.map(|time| (Instant::now(), time));
+        while let Some(found) = self.next(limits.path_max, limits.grab_max, false) {
+            let go_on = match (found, self.stats.solutions.last(), &self.path) {
+                (true, Some(solution), Some(path)) => on_solution(solution, path),
+                _ => true,
+            };
+            if !go_on
+                || (limits.any && found)
+                || self.done.len() > limits.done_max
+                || deadline.is_some_and(|(start, time)| start.elapsed() >= time)
+            {
+                break;
+            }
+        }
+        self.path()
+    }
+
     pub fn next(
+        &mut self,
+        path_upper_limit: usize,
+        input_upper_limit: usize,
+        debug_output: bool,
+    ) -> Option<bool> {
+        let clock = self.stats.clock();
+        let found = self.expand(path_upper_limit, input_upper_limit, debug_output);
+        self.update_peaks();
+        self.stats.lap(clock, |timings| &mut timings.total);
+
+        if let (Some(true), Some(path)) = (found, &self.path) {
+            self.stats.solutions.push(SolutionFound {
+                len: path.len(),
+                expanded: self.stats.expanded,
+                done: self.done.len(),
+                elapsed: self.stats.timings.as_ref().map(|timings| timings.total),
+            });
+        }
+        found
+    }
+
+    fn update_peaks(&mut self) {
+        self.stats.bank_peak = self.stats.bank_peak.max(self.bank.len());
+        self.stats.done_peak = self.stats.done_peak.max(self.done.len());
+    }
+
+    fn expand(
         &mut self,
         mut path_upper_limit: usize,
         input_upper_limit: usize,
@@ -110,7 +325,7 @@ impl Solver {
         let grade = *self.bank.grades().next()?;
         let mut input = self.bank.split_off(grade, input_upper_limit)?;
 
-        let prioritize = self.bank.len() > 0;
+        let prioritize = !self.bank.is_empty();
 
         while let Some(path) = input.pop() {
             self.game.set_path(path.iter());
@@ -121,8 +336,20 @@ impl Solver {
             #[cfg(debug)]
             let mold = self.game.get_invariant();
 
-            let moves = self.game.get_all_moves();
-            debug_assert!(moves.len() > 0);
+            let clock = self.stats.clock();
+            let mut moves = self.game.get_all_moves();
+            self.sorter.sort(&self.game, &mut moves);
+            self.stats
+                .lap(clock, |timings| &mut timings.move_generation);
+            debug_assert!(!moves.is_empty());
+            self.stats.expanded += 1;
+            self.stats.generated += moves.len();
+
+            let parent = self.tracer.as_ref().map_or(ROOT_ID, |tracer| {
+                let id = tracer.id_of(&self.game.get_invariant());
+                debug_assert!(id.is_some(), "Expanded positions should be traced!");
+                id.unwrap_or(ROOT_ID)
+            });
 
             for mv in moves {
                 self.game.backward(mark);
@@ -131,19 +358,38 @@ impl Solver {
                 #[cfg(debug)]
                 debug_assert_eq!(mold, self.game.get_invariant());
 
-                self.game.move_card(mv.giver(), mv.taker());
+                self.game.apply(&mv);
                 self.game.move_cards_auto();
 
+                let depth = self.game.path().len();
+
                 // Skip over long solutions.
                 let estm_len = self.game.estimate_path_len();
                 if estm_len >= path_upper_limit {
+                    self.stats.too_long += 1;
+                    if let Some(tracer) = &mut self.tracer {
+                        let event = TraceEvent {
+                            id: ROOT_ID,
+                            parent,
+                            grade,
+                            mv,
+                            estimate: estm_len,
+                            depth,
+                            outcome: Outcome::TooLong,
+                        };
+                        tracer.record(None, event);
+                    }
                     continue;
                 }
 
                 // State Analysis.
+                let mut outcome = Outcome::DeadEnd;
+                let mut kept = None;
                 if self.game.has_next_move() {
                     // Not solved yet.
+                    let clock = self.stats.clock();
                     let key = self.game.get_invariant();
+                    self.stats.lap(clock, |timings| &mut timings.invariants);
                     if match self.done.get(&key) {
                         None => true,
                         Some(&min_len) => estm_len < min_len,
@@ -151,18 +397,41 @@ impl Solver {
                         // Keep this path.
                         self.done.insert(key, estm_len);
                         let grade = if prioritize {
-                            game_priority(&self.game)
+                            self.priority.grade(&self.game)
                         } else {
                             0
                         };
                         self.bank.add(grade, self.game.path().clone());
+                        self.stats.kept += 1;
+                        outcome = Outcome::Kept;
+                        kept = Some(key);
 
                         self.game.unfold();
+                    } else {
+                        self.stats.duplicates += 1;
+                        outcome = Outcome::Duplicate;
                     }
                 }
 
                 let sol_len = self.game.path().len();
-                if sol_len < path_upper_limit && self.game.is_done() {
+                let solved = sol_len < path_upper_limit && self.game.is_done();
+                if !solved && outcome == Outcome::DeadEnd {
+                    self.stats.dead_ends += 1;
+                }
+                if let Some(tracer) = &mut self.tracer {
+                    let event = TraceEvent {
+                        id: ROOT_ID,
+                        parent,
+                        grade,
+                        mv,
+                        estimate: estm_len,
+                        depth: if solved { sol_len } else { depth },
+                        outcome: if solved { Outcome::Solved } else { outcome },
+                    };
+                    tracer.record(kept, event);
+                }
+
+                if solved {
                     // Solved!
                     self.path = Some(self.game.path().clone());
 
@@ -176,7 +445,10 @@ impl Solver {
                     }
 
                     // Cleaning. Get rid of long paths.
+                    self.update_peaks();
                     let removed = clean_bank(&mut self.bank, &mut self.game, sol_len);
+                    self.stats.cleanings += 1;
+                    self.stats.cleaned += removed;
                     if debug_output {
                         println!("Cleaning:");
                         println!("    bank: {}; removed: {}", self.bank.len(), removed);
@@ -201,3 +473,114 @@ impl Solver {
         Some(false)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::freecell::basis::*;
+
+    #[test]
+    fn start_from_position() {
+        let mut game = Game::new();
+        game.deal(&deck::deal(173205951));
+        game.move_cards_auto();
+        game.move_card(PILE_START + 7, PILE_START + 1);
+        game.move_card(PILE_START + 3, PILE_START + 1);
+        game.move_card(PILE_START + 7, CELL_START);
+
+        let limits = Limits {
+            any: true,
+            ..Limits::default()
+        };
+        let mut solver = Solver::new();
+        solver.start_from(&game);
+        let path = solver.solve(&limits).expect("Should be solved!").clone();
+
+        game.forward(path.iter());
+        assert!(game.is_done());
+    }
+
+    #[test]
+    fn anytime() {
+        let mut solver = Solver::new();
+        solver.deal(1);
+        let mut lens = Vec::new();
+        let path = solver
+            .solve_anytime(&Limits::default(), |found, path| {
+                assert_eq!(found.len, path.len());
+                lens.push(found.len);
+                lens.len() < 3
+            })
+            .expect("Should be solved!");
+
+        assert_eq!(3, lens.len());
+        assert_eq!(Some(&path.len()), lens.last());
+        assert!(lens.windows(2).all(|pair| pair[0] > pair[1]));
+        assert!(solver
+            .stats()
+            .solutions
+            .iter()
+            .all(|found| found.elapsed.is_none()));
+    }
+
+    #[test]
+    fn stats() {
+        let mut solver = Solver::new();
+        solver.set_timed(true);
+        solver.deal(1);
+        let limits = Limits {
+            any: true,
+            ..Limits::default()
+        };
+        // The search goes on for shorter solutions.
+        solver.solve(&limits).expect("Should be solved!");
+        let path = solver.solve(&limits).expect("Should be improved!").clone();
+
+        let stats = solver.stats();
+        assert!(stats.expanded > 0 && stats.generated > stats.expanded);
+        assert!(
+            stats.generated >= stats.kept + stats.duplicates + stats.too_long + stats.dead_ends
+        );
+        assert!(stats.done_peak >= solver.done().len());
+        assert!(stats.cleanings > 0 && stats.cleaned > 0);
+        // Each solution improves on the previous one.
+        let lens: Vec<usize> = stats.solutions.iter().map(|found| found.len).collect();
+        assert_eq!(2, lens.len());
+        assert_eq!(Some(&path.len()), lens.last());
+        assert!(lens.windows(2).all(|pair| pair[0] > pair[1]));
+        let timings = stats.timings.as_ref().expect("Should be timed!");
+        assert!(timings.total >= timings.move_generation + timings.invariants);
+
+        solver.set_timed(false);
+        solver.deal(1);
+        assert_eq!(&SolverStats::default(), solver.stats());
+    }
+
+    #[test]
+    fn move_seeds() {
+        use crate::freecell::trace::{TraceEvent, Tracer};
+        use std::sync::{Arc, Mutex};
+
+        // The moves of the first expanded positions, in the order they are tried.
+        let expansion_order = |order: MoveOrder| {
+            let moves = Arc::new(Mutex::new(Vec::new()));
+            let sink = Arc::clone(&moves);
+            let mut solver = Solver::new();
+            solver.set_tracer(Some(Tracer::new(move |event: &TraceEvent| {
+                sink.lock().unwrap().push(event.mv.clone())
+            })));
+            solver.set_move_order(order);
+            solver.deal(1);
+            solver.next(Limits::default().path_max, 10, false);
+            let moves = moves.lock().unwrap().clone();
+            moves
+        };
+
+        let standard = expansion_order(MoveOrder::Standard);
+        let first = expansion_order(MoveOrder::Random(1));
+        assert!(first.len() > 1);
+        assert_eq!(first, expansion_order(MoveOrder::Random(1)));
+        assert_ne!(first, expansion_order(MoveOrder::Random(2)));
+        assert_ne!(first, standard);
+    }
+}
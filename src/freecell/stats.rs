@@ -0,0 +1,135 @@
+//! # Solver statistics
+//! Counters maintained by [`Solver`](crate::freecell::Solver) while searching.
+
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// The time spent in the search stages.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Timings {
+    /// The total time spent in [`Solver::next`](crate::freecell::Solver::next).
+    pub total: Duration,
+    pub move_generation: Duration,
+    pub invariants: Duration,
+}
+
+impl Timings {
+    /// Returns the time spent in everything else: making moves, banking paths, cleaning and so on.
+    pub fn bookkeeping(&self) -> Duration {
+        self.total
+            .saturating_sub(self.move_generation)
+            .saturating_sub(self.invariants)
+    }
+}
+
+/// A solution found by the search, each one shorter than the previous.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SolutionFound {
+    pub len: usize,
+    /// The number of positions expanded so far.
+    pub expanded: usize,
+    /// The number of positions explored so far, see [`Solver::done`](crate::freecell::Solver::done).
+    pub done: usize,
+    /// The search time so far, if timed.
+    pub elapsed: Option<Duration>,
+}
+
+/// Search counters, reset when a new search starts.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SolverStats {
+    /// The number of positions taken from the bank and expanded.
+    pub expanded: usize,
+    /// The number of moves generated from the expanded positions.
+    pub generated: usize,
+    /// The number of positions banked for expansion.
+    pub kept: usize,
+    /// The number of positions reached before with a shorter estimate.
+    pub duplicates: usize,
+    /// The number of positions whose estimated solution length is over the limit.
+    pub too_long: usize,
+    /// The number of unsolved positions without moves.
+    pub dead_ends: usize,
+    /// The number of bank cleanings after solutions were found.
+    pub cleanings: usize,
+    /// The number of paths removed from the bank by cleanings.
+    pub cleaned: usize,
+    pub bank_peak: usize,
+    pub done_peak: usize,
+    /// Set if the search is timed, see [`Solver::set_timed`](crate::freecell::Solver::set_timed).
+    pub timings: Option<Timings>,
+    pub solutions: Vec<SolutionFound>,
+}
+
+impl SolverStats {
+    pub fn new(timed: bool) -> Self {
+        Self {
+            timings: if timed {
+                Some(Timings::default())
+            } else {
+                None
+            },
+            ..Self::default()
+        }
+    }
+
+    /// Reads the clock if the search is timed. The clock isn't available on all targets (e.g. wasm32).
+    pub(crate) fn clock(&self) -> Option<Instant> {
+        self.timings.as_ref().map(|_| Instant::now())
+    }
+
+    /// Adds the time elapsed since the clock reading to one of the timings.
+    pub(crate) fn lap(
+        &mut self,
+        clock: Option<Instant>,
+        timing: impl FnOnce(&mut Timings) -> &mut Duration,
+    ) {
+        if let (Some(timings), Some(clock)) = (&mut self.timings, clock) {
+            *timing(timings) += clock.elapsed();
+        }
+    }
+}
+
+impl fmt::Display for SolverStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Expanded: {}", self.expanded)?;
+        writeln!(f, "Generated: {}", self.generated)?;
+        writeln!(
+            f,
+            "    kept: {}, duplicates: {}, too long: {}, dead ends: {}",
+            self.kept, self.duplicates, self.too_long, self.dead_ends
+        )?;
+        writeln!(
+            f,
+            "Cleanings: {}, removed: {}",
+            self.cleanings, self.cleaned
+        )?;
+        writeln!(
+            f,
+            "Peak bank: {}, peak done: {}",
+            self.bank_peak, self.done_peak
+        )?;
+        if let Some(timings) = &self.timings {
+            writeln!(
+                f,
+                "Time: {:?} (move generation: {:?}, invariants: {:?}, bookkeeping: {:?})",
+                timings.total,
+                timings.move_generation,
+                timings.invariants,
+                timings.bookkeeping()
+            )?;
+        }
+        writeln!(f, "Solutions: {}", self.solutions.len())?;
+        for found in &self.solutions {
+            write!(
+                f,
+                "    {} moves after {} expanded, {} explored",
+                found.len, found.expanded, found.done
+            )?;
+            match found.elapsed {
+                Some(elapsed) => writeln!(f, " in {:?}", elapsed)?,
+                None => writeln!(f)?,
+            }
+        }
+        Ok(())
+    }
+}
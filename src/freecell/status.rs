@@ -0,0 +1,191 @@
+//! # Position status
+
+use crate::freecell::autoplay::AutoPlay;
+use crate::freecell::game::Game;
+use crate::freecell::invariant::Key64;
+use std::collections::HashSet;
+
+/// The status of a game position.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Status {
+    /// All cards are in their foundations.
+    Won,
+    /// No progress possible: the game can't be won from the position.
+    Lost,
+    /// The position is neither won nor proven to be lost.
+    Open,
+}
+
+/// Copies the position of a game, so that moves taking back the path are not excluded.
+/// The copy plays automatically with a safe policy: an unsafe one could lose a won position.
+fn position_of(game: &Game) -> Game {
+    let mut position = Game::new();
+    position.set_cell_num(game.cell_num());
+    position.set_auto_play(AutoPlay::Horne);
+    position.set_position(game);
+    position
+}
+
+/// Returns [`true`] for a common trap: no empty cells or piles,
+/// and no moves to a tableau or a base.
+pub fn is_trapped(game: &Game) -> bool {
+    let position = position_of(game);
+    !position.is_done()
+        && position.count_empty() == 0
+        && !position.has_move_to_tableau()
+        && !position.has_move_to_base()
+}
+
+/// Classifies a position as won, lost or open.
+/// Besides the [`is_trapped`] check, all positions reachable from the given one are explored,
+/// unless there are more than `done_max` of them. The position is lost if none of them is won.
+pub fn status(game: &Game, done_max: usize) -> Status {
+    if game.is_done() {
+        return Status::Won;
+    }
+    if is_trapped(game) {
+        return Status::Lost;
+    }
+
+    let mut game = position_of(game);
+    game.move_cards_auto();
+    if game.is_done() {
+        return Status::Open;
+    }
+
+    let mut done: HashSet<Key64> = HashSet::new();
+    let mut bank = vec![game.path().clone()];
+    done.insert(game.get_invariant());
+
+    while let Some(path) = bank.pop() {
+        if done.len() > done_max {
+            return Status::Open;
+        }
+
+        game.set_path(path.iter());
+        let mark = path.len();
+        for mv in game.get_all_moves() {
+            game.backward(mark);
+            game.apply(&mv);
+            game.move_cards_auto();
+
+            if game.is_done() {
+                return Status::Open;
+            }
+            if done.insert(game.get_invariant()) {
+                bank.push(game.path().clone());
+            }
+        }
+    }
+
+    Status::Lost
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deck;
+    use crate::deck::{Card, Rank, Suit};
+    use crate::freecell::basis::*;
+    use crate::freecell::game::path_from_hex;
+
+    fn trap(cells: usize) -> Game {
+        // Kings and nines on top with fives, sevens and threes underneath.
+        let mut cards = deck::new();
+        let mut free: Vec<usize> = (0..deck::CARD_NUM).collect();
+        let top = [
+            (48, Rank::King),
+            (44, Rank::Nine),
+            (40, Rank::Five),
+            (36, Rank::Seven),
+            (28, Rank::Three),
+        ];
+        for &(start, rank) in &top {
+            for suit in Suit::ALL.iter() {
+                cards[start + suit.index()] = Card::new(rank, *suit);
+                free.retain(|&i| i != start + suit.index());
+            }
+        }
+        let rest = Card::all().filter(|card| top.iter().all(|&(_, rank)| card.rank() != rank));
+        for (index, card) in free.into_iter().zip(rest) {
+            cards[index] = card;
+        }
+
+        let mut game = Game::new();
+        game.deal(&cards);
+        for i in 0..cells {
+            game.move_card(PILE_START + 4 + i, CELL_START + i);
+        }
+        game
+    }
+
+    /// Lays the cards out at the spots bottom up, picking them from a heap in the cells.
+    fn layout(spots: &[(usize, &str)]) -> Game {
+        let mut game = Game::new();
+        game.deal(&deck::new());
+        for pile in PILE_START..PILE_END {
+            while game.card_at(pile).is_some() {
+                game.move_card(pile, CELL_START);
+            }
+        }
+        let (heap, spare) = (CELL_START, CELL_START + 1);
+        for &(spot, cards) in spots {
+            for card in deck::parse(cards).expect("Should be parsed!") {
+                let (from, to) = if game.cards_at(heap).any(|c| c == card) {
+                    (heap, spare)
+                } else {
+                    (spare, heap)
+                };
+                while game.card_at(from) != Some(card) {
+                    game.move_card(from, to);
+                }
+                game.move_card(from, spot);
+            }
+        }
+        game
+    }
+
+    #[test]
+    fn statuses() {
+        let mut game = Game::new();
+        game.deal(&deck::deal(173205951));
+        assert!(!is_trapped(&game));
+        assert_eq!(Status::Open, status(&game, 1000));
+
+        let path = path_from_hex(
+            "4871317c7b737478653d35d53d3e39c8606c656a60e04e46e6461e1f16f6e6213ed35d535f575171f1272f262b2aead35d5e590939c94a083a395c56060a4204020205050beb6b1b1a1e12e21e1b17152b186869d9f9e96a6b2a2b6a6b2a2b4a38c818787958595a49686b28",
+        )
+        .expect("Should be parsed!");
+        game.forward(path.iter());
+        assert_eq!(Status::Won, status(&game, 1000));
+
+        let game = trap(CELL_NUM);
+        assert!(is_trapped(&game));
+        assert_eq!(Status::Lost, status(&game, 0));
+
+        let game = trap(CELL_NUM - 1);
+        assert!(!is_trapped(&game));
+        assert_eq!(Status::Lost, status(&game, 1000));
+        assert_eq!(Status::Open, status(&game, 0));
+
+        // With one cell, 3♣ and 2♦ have to go on 4♥ to free A♦, but the standard auto-play takes 4♥ home first.
+        let mut game = layout(&[
+            (BASE_START, "AS 2S 3S"),
+            (BASE_START + 2, "AC 2C"),
+            (BASE_START + 3, "AH 2H 3H"),
+            (PILE_START, "KD QD JD TD 9D 8D"),
+            (PILE_START + 1, "AD KC 3C 2D"),
+            (PILE_START + 2, "QC JC TC 9C 8C 7C 6C 5C 4C KH"),
+            (PILE_START + 3, "KS QS JS TS 9S 8S 7S 6S 5S 4S JH QH"),
+            (PILE_START + 4, "5H 6H TH"),
+            (PILE_START + 5, "7H 8H"),
+            (PILE_START + 6, "6D 5D 4D 3D"),
+            (PILE_START + 7, "7D 9H 4H"),
+        ]);
+        game.set_cell_num(1);
+        assert_eq!(Status::Open, status(&game, 1000));
+        game.move_cards_auto();
+        assert_eq!(Status::Lost, status(&game, 1000));
+    }
+}
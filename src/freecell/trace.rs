@@ -0,0 +1,217 @@
+//! # Search trace
+//! Records what [`Solver::next`](crate::freecell::Solver::next) does with every position it generates,
+//! to find out which branches were followed and why the others were dropped.
+//!
+//! With the `serde` feature, events serialize to JSON objects, e.g.
+//! `{"id":7,"parent":2,"grade":0,"move":"3c","estimate":74,"depth":3,"outcome":"kept"}`.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+
+use crate::freecell::game::Move;
+use crate::freecell::invariant::Key64;
+
+/// The id of the starting position, the parent of the first expanded moves.
+pub const ROOT_ID: u64 = 0;
+
+/// What became of a generated position.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Outcome {
+    /// Banked for expansion.
+    Kept,
+    /// Pruned: the estimated solution length is over the limit.
+    TooLong,
+    /// Pruned: the position was reached before with a shorter estimate.
+    Duplicate,
+    /// Pruned: no moves left.
+    DeadEnd,
+    /// The game is won.
+    Solved,
+}
+
+impl Outcome {
+    pub const ALL: [Outcome; 5] = [
+        Outcome::Kept,
+        Outcome::TooLong,
+        Outcome::Duplicate,
+        Outcome::DeadEnd,
+        Outcome::Solved,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Outcome::Kept => "kept",
+            Outcome::TooLong => "too_long",
+            Outcome::Duplicate => "duplicate",
+            Outcome::DeadEnd => "dead_end",
+            Outcome::Solved => "solved",
+        }
+    }
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A position generated by a move from an expanded one.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TraceEvent {
+    pub id: u64,
+    /// The id of the expanded position.
+    pub parent: u64,
+    /// The bank grade of the expanded position.
+    pub grade: usize,
+    /// The move from the expanded position, followed by automatic moves to bases.
+    #[cfg_attr(feature = "serde", serde(rename = "move"))]
+    pub mv: Move,
+    /// See [`Game::estimate_path_len`](crate::freecell::Game::estimate_path_len).
+    pub estimate: usize,
+    /// The path length of the position, or of the solution found.
+    pub depth: usize,
+    pub outcome: Outcome,
+}
+
+/// Numbers the positions of a search and passes the events to a sink.
+pub struct Tracer {
+    sink: Box<dyn FnMut(&TraceEvent) + Send>,
+    ids: HashMap<Key64, u64>,
+    next_id: u64,
+}
+
+impl Tracer {
+    pub fn new(sink: impl FnMut(&TraceEvent) + Send + 'static) -> Self {
+        Self {
+            sink: Box::new(sink),
+            ids: HashMap::new(),
+            next_id: ROOT_ID + 1,
+        }
+    }
+
+    /// Forgets the positions of the previous search.
+    pub(crate) fn start(&mut self, root: Key64) {
+        self.ids.clear();
+        self.ids.insert(root, ROOT_ID);
+    }
+
+    /// Returns the id of a kept position, [`None`] for a position unknown to the tracer.
+    pub(crate) fn id_of(&self, key: &Key64) -> Option<u64> {
+        self.ids.get(key).copied()
+    }
+
+    /// Numbers and records a generated position.
+    /// Kept positions are identified by their invariant when expanded later on.
+    pub(crate) fn record(&mut self, kept: Option<Key64>, mut event: TraceEvent) {
+        event.id = self.next_id;
+        self.next_id += 1;
+        if let Some(key) = kept {
+            self.ids.insert(key, event.id);
+        }
+        (self.sink)(&event);
+    }
+}
+
+/// Aggregated trace events.
+#[derive(Clone, Debug, Default)]
+pub struct TraceSummary {
+    pub events: usize,
+    /// The number of expanded positions per grade.
+    pub grades: BTreeMap<usize, usize>,
+    pub outcomes: BTreeMap<Outcome, usize>,
+    /// The number of generated positions per path length.
+    pub depths: BTreeMap<usize, usize>,
+    expanded: BTreeSet<u64>,
+}
+
+impl TraceSummary {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, event: &TraceEvent) {
+        self.events += 1;
+        if self.expanded.insert(event.parent) {
+            *self.grades.entry(event.grade).or_default() += 1;
+        }
+        *self.outcomes.entry(event.outcome).or_default() += 1;
+        *self.depths.entry(event.depth).or_default() += 1;
+    }
+}
+
+impl fmt::Display for TraceSummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Events: {}", self.events)?;
+        writeln!(f, "Expanded per grade:")?;
+        for (grade, count) in &self.grades {
+            writeln!(f, "    {}: {}", grade, count)?;
+        }
+        writeln!(f, "Outcomes:")?;
+        for outcome in &Outcome::ALL {
+            writeln!(
+                f,
+                "    {}: {}",
+                outcome,
+                self.outcomes.get(outcome).copied().unwrap_or(0)
+            )?;
+        }
+        writeln!(f, "Depths:")?;
+        for (depth, count) in &self.depths {
+            writeln!(f, "    {}: {}", depth, count)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::freecell::{Limits, Solver};
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn trace() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&events);
+
+        let mut solver = Solver::new();
+        solver.set_tracer(Some(Tracer::new(move |event: &TraceEvent| {
+            sink.lock().unwrap().push(event.clone())
+        })));
+        solver.deal(1);
+        let limits = Limits {
+            any: true,
+            ..Limits::default()
+        };
+        let path = solver.solve(&limits).expect("Should be solved!").clone();
+
+        let events = events.lock().unwrap();
+        let mut summary = TraceSummary::new();
+        for event in events.iter() {
+            summary.add(event);
+        }
+        assert_eq!(1, summary.outcomes[&Outcome::Solved]);
+        assert_eq!(events.len(), summary.events);
+        assert_eq!(summary.events, summary.depths.values().sum::<usize>());
+        assert!(summary.outcomes[&Outcome::Duplicate] > 0);
+
+        // The solution can be followed back through the parents.
+        let solved = events.last().expect("Should be recorded!");
+        assert_eq!(Outcome::Solved, solved.outcome);
+        let mut line = vec![solved];
+        while line[line.len() - 1].parent != ROOT_ID {
+            let parent = line[line.len() - 1].parent;
+            line.push(
+                events
+                    .iter()
+                    .find(|event| event.id == parent)
+                    .expect("Should be known!"),
+            );
+        }
+        assert!(line.iter().rev().all(|event| path.contains(&event.mv)));
+        assert!(line[1..].iter().all(|event| event.outcome == Outcome::Kept));
+    }
+}
@@ -1,7 +1,11 @@
+pub mod cache;
 pub mod deck;
 pub mod freecell;
 pub mod util;
 
+#[cfg(feature = "serde")]
+mod serialization;
+
 #[cfg(test)]
 mod tests {
     #[test]
@@ -0,0 +1,420 @@
+//! Serde support, enabled by the `serde` feature.
+//! Human-readable formats get card strings and hex moves, binary formats get compact bytes.
+
+use crate::deck::{self, Card};
+use crate::freecell::{
+    dealt_len, desk_range, hex_to_spot, is_deck, AutoPlay, Game, Key64, Move, Path, CELL_NUM,
+    DESK_SIZE, KEY_SIZE, PILE_NUM, PILE_START,
+};
+use serde::de::{self, Deserializer, SeqAccess, Visitor};
+use serde::ser::{SerializeStruct, Serializer};
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
+use std::fmt;
+
+impl Serialize for Card {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.collect_str(self)
+        } else {
+            serializer.serialize_u8(u8::from(*self))
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Card {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            let s = String::deserialize(deserializer)?;
+            s.parse().map_err(de::Error::custom)
+        } else {
+            Card::try_from(u8::deserialize(deserializer)?).map_err(de::Error::custom)
+        }
+    }
+}
+
+impl Serialize for Move {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let (giver, taker) = (self.giver(), self.taker());
+        if serializer.is_human_readable() {
+            serializer.collect_str(&format_args!("{}{}", giver.to_hex(), taker.to_hex()))
+        } else {
+            (giver.index() as u8, taker.index() as u8).serialize(serializer)
+        }
+    }
+}
+
+fn to_move<E: de::Error>(giver: usize, taker: usize) -> Result<Move, E> {
+    if giver < DESK_SIZE && taker < DESK_SIZE && giver != taker {
+        Ok(Move::at(giver, taker))
+    } else {
+        Err(E::custom(format_args!(
+            "invalid move: {} -> {}",
+            giver, taker
+        )))
+    }
+}
+
+impl<'de> Deserialize<'de> for Move {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            let s = String::deserialize(deserializer)?;
+            let mut spots = s.chars().map(hex_to_spot);
+            match (spots.next(), spots.next(), spots.next()) {
+                (Some(Some(giver)), Some(Some(taker)), None) => to_move(giver, taker),
+                _ => Err(de::Error::custom(format_args!("invalid move: '{}'", s))),
+            }
+        } else {
+            let (giver, taker) = <(u8, u8)>::deserialize(deserializer)?;
+            to_move(giver as usize, taker as usize)
+        }
+    }
+}
+
+/// A pile of encoded cards: a card string or bytes.
+struct PileRef<'a>(&'a [u8]);
+struct PileBuf(Vec<u8>);
+
+impl Serialize for PileRef<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            let cards = self.0.iter().map(|&card| Card::from_u8(card));
+            serializer.collect_str(&cards.map(|card| card.to_string()).collect::<String>())
+        } else {
+            serializer.serialize_bytes(self.0)
+        }
+    }
+}
+
+struct PileVisitor;
+
+impl<'de> Visitor<'de> for PileVisitor {
+    type Value = PileBuf;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a card string or card bytes")
+    }
+
+    fn visit_str<E: de::Error>(self, s: &str) -> Result<PileBuf, E> {
+        let cards = deck::parse(s).map_err(E::custom)?;
+        Ok(PileBuf(cards.into_iter().map(u8::from).collect()))
+    }
+
+    fn visit_bytes<E: de::Error>(self, bytes: &[u8]) -> Result<PileBuf, E> {
+        for &card in bytes {
+            Card::try_from(card).map_err(E::custom)?;
+        }
+        Ok(PileBuf(bytes.to_vec()))
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, bytes: Vec<u8>) -> Result<PileBuf, E> {
+        self.visit_bytes(&bytes)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<PileBuf, A::Error> {
+        let mut pile = Vec::new();
+        while let Some(card) = seq.next_element::<u8>()? {
+            pile.push(card);
+        }
+        self.visit_bytes(&pile)
+    }
+}
+
+impl<'de> Deserialize<'de> for PileBuf {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_str(PileVisitor)
+        } else {
+            deserializer.deserialize_bytes(PileVisitor)
+        }
+    }
+}
+
+impl Serialize for Game {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let desk: Vec<PileRef> = self.desk().iter().map(|pile| PileRef(pile)).collect();
+        let mut state = serializer.serialize_struct("Game", 4)?;
+        state.serialize_field("desk", &desk)?;
+        state.serialize_field("path", self.path())?;
+        state.serialize_field("cell_num", &self.cell_num())?;
+        state.serialize_field("auto_play", &self.auto_play())?;
+        state.end()
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename = "Game")]
+struct GameData {
+    desk: Vec<PileBuf>,
+    path: Path,
+    cell_num: usize,
+    #[serde(default)]
+    auto_play: AutoPlay,
+}
+
+impl<'de> Deserialize<'de> for Game {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let data = GameData::deserialize(deserializer)?;
+        if data.desk.len() != DESK_SIZE {
+            return Err(de::Error::invalid_length(data.desk.len(), &"16 piles"));
+        }
+        if data.cell_num > CELL_NUM {
+            return Err(de::Error::custom(format_args!(
+                "invalid cell number: {}",
+                data.cell_num
+            )));
+        }
+        let mut desk: Vec<Vec<u8>> = data.desk.into_iter().map(|pile| pile.0).collect();
+
+        // The path should lead to the desk from a deal: take it back.
+        for mv in data.path.iter().rev() {
+            let card = desk[mv.taker().index()]
+                .pop()
+                .ok_or_else(|| de::Error::custom("path does not lead to the desk"))?;
+            desk[mv.giver().index()].push(card);
+        }
+        let is_dealt = desk_range().all(|spot| match spot.checked_sub(PILE_START) {
+            Some(pile) => desk[spot].len() == dealt_len(pile),
+            None => desk[spot].is_empty(),
+        });
+        let cards: Vec<Card> = (0..deck::CARD_NUM)
+            .filter(|_| is_dealt)
+            .map(|i| Card::from_u8(desk[PILE_START + i % PILE_NUM][i / PILE_NUM]))
+            .collect();
+        if !is_deck(&cards) {
+            return Err(de::Error::custom("desk is not reachable from a deal"));
+        }
+
+        // Replay the path, so that each move is checked.
+        let mut game = Game::new();
+        game.set_cell_num(data.cell_num);
+        game.set_auto_play(data.auto_play);
+        game.deal(&cards);
+        for mv in &data.path {
+            if !game.is_valid_move(mv.giver().index(), mv.taker().index()) {
+                return Err(de::Error::custom(format_args!(
+                    "invalid move in path: {}{}",
+                    mv.giver().to_hex(),
+                    mv.taker().to_hex()
+                )));
+            }
+            game.apply(mv);
+        }
+        Ok(game)
+    }
+}
+
+impl Serialize for Key64 {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            let hex: String = self
+                .as_bytes()
+                .iter()
+                .map(|b| format!("{:02x}", b))
+                .collect();
+            serializer.serialize_str(&hex)
+        } else {
+            serializer.serialize_bytes(self.as_bytes())
+        }
+    }
+}
+
+struct KeyVisitor;
+
+impl<'de> Visitor<'de> for KeyVisitor {
+    type Value = Key64;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} bytes or their hex string", KEY_SIZE)
+    }
+
+    fn visit_str<E: de::Error>(self, s: &str) -> Result<Key64, E> {
+        if s.len() != 2 * KEY_SIZE || !s.is_ascii() {
+            return Err(E::invalid_length(s.len(), &self));
+        }
+        let bytes = (0..KEY_SIZE)
+            .map(|i| u8::from_str_radix(&s[2 * i..2 * i + 2], 16))
+            .collect::<Result<Vec<u8>, _>>()
+            .map_err(E::custom)?;
+        self.visit_bytes(&bytes)
+    }
+
+    fn visit_bytes<E: de::Error>(self, bytes: &[u8]) -> Result<Key64, E> {
+        if bytes.len() != KEY_SIZE {
+            return Err(E::invalid_length(bytes.len(), &self));
+        }
+        let mut key = Key64::new();
+        for (i, &b) in bytes.iter().enumerate() {
+            key.put(i, b);
+        }
+        Ok(key)
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, bytes: Vec<u8>) -> Result<Key64, E> {
+        self.visit_bytes(&bytes)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Key64, A::Error> {
+        let mut bytes = Vec::with_capacity(KEY_SIZE);
+        while let Some(b) = seq.next_element::<u8>()? {
+            bytes.push(b);
+        }
+        self.visit_bytes(&bytes)
+    }
+}
+
+impl<'de> Deserialize<'de> for Key64 {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_str(KeyVisitor)
+        } else {
+            deserializer.deserialize_bytes(KeyVisitor)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::freecell::{
+        hint, path_from_hex, Hint, Limits, Outcome, Status, TraceEvent, BASE_START, CELL_START,
+        PILE_START,
+    };
+
+    fn game() -> Game {
+        let mut game = Game::new();
+        game.set_cell_num(3);
+        game.deal(&deck::deal(173205951));
+        game.forward(path_from_hex("4871317c").expect("Should be parsed!").iter());
+        game
+    }
+
+    /// Serializes the game with its desk edited.
+    fn with_desk(game: &Game, edit: impl FnOnce(&mut Vec<Vec<Card>>)) -> String {
+        let mut desk: Vec<Vec<Card>> = (0..DESK_SIZE)
+            .map(|spot| game.cards_at(spot).collect())
+            .collect();
+        edit(&mut desk);
+        let mut value = serde_json::to_value(game).unwrap();
+        value["desk"] = desk
+            .iter()
+            .map(|pile| pile.iter().map(Card::to_string).collect::<String>())
+            .collect();
+        value.to_string()
+    }
+
+    fn assert_same(a: &Game, b: &Game) {
+        assert_eq!(a.desk(), b.desk());
+        assert_eq!(a.path(), b.path());
+        assert_eq!(a.cell_num(), b.cell_num());
+        assert_eq!(a.auto_play(), b.auto_play());
+    }
+
+    #[test]
+    fn text() {
+        let card = Card::from_u8(0);
+        assert_eq!("\"A♠\"", serde_json::to_string(&card).unwrap());
+        assert_eq!(card, serde_json::from_str::<Card>("\"AS\"").unwrap());
+        assert!(serde_json::from_str::<Card>("\"X\"").is_err());
+
+        let mv = Move::at(PILE_START + 4, BASE_START);
+        assert_eq!("\"48\"", serde_json::to_string(&mv).unwrap());
+        assert_eq!(mv, serde_json::from_str::<Move>("\"48\"").unwrap());
+        assert!(serde_json::from_str::<Move>("\"44\"").is_err());
+        assert!(serde_json::from_str::<Move>("\"4\"").is_err());
+
+        let mut game = game();
+        let json = serde_json::to_string(&game).unwrap();
+        assert!(json.contains("\"path\":[\"48\",\"71\",\"31\",\"7c\"]"));
+        let mut other: Game = serde_json::from_str(&json).unwrap();
+        assert_same(&game, &other);
+        game.rewind();
+        other.rewind();
+        assert_same(&game, &other);
+
+        let key = game.get_invariant();
+        let json = serde_json::to_string(&key).unwrap();
+        assert_eq!(2 + 2 * KEY_SIZE, json.len());
+        assert_eq!(key, serde_json::from_str(&json).unwrap());
+
+        let json = serde_json::to_string(&Status::Lost).unwrap();
+        assert_eq!(Status::Lost, serde_json::from_str(&json).unwrap());
+
+        let hint = hint(&game, 1000).expect("Should be found!");
+        let other: Hint = serde_json::from_str(&serde_json::to_string(&hint).unwrap()).unwrap();
+        assert_eq!((hint.mv, hint.winning), (other.mv, other.winning));
+
+        let event = TraceEvent {
+            id: 7,
+            parent: 2,
+            grade: 0,
+            mv: path_from_hex("3c").unwrap()[0].clone(),
+            estimate: 74,
+            depth: 3,
+            outcome: Outcome::Kept,
+        };
+        let json = serde_json::to_string(&event).unwrap();
+        assert_eq!(
+            "{\"id\":7,\"parent\":2,\"grade\":0,\"move\":\"3c\",\"estimate\":74,\"depth\":3,\"outcome\":\"kept\"}",
+            json
+        );
+        assert_eq!(event, serde_json::from_str(&json).unwrap());
+
+        let limits: Limits = serde_json::from_str("{\"done_max\":1000}").unwrap();
+        assert_eq!(1000, limits.done_max);
+        assert_eq!(Limits::default().path_max, limits.path_max);
+
+        assert!(serde_json::from_str::<Game>("{\"desk\":[],\"path\":[],\"cell_num\":4}").is_err());
+        let json = serde_json::to_string(&game)
+            .unwrap()
+            .replace("[]", "[\"c8\"]");
+        assert!(serde_json::from_str::<Game>(&json).is_err());
+    }
+
+    #[test]
+    fn unreachable() {
+        let game = game();
+        let json = with_desk(&game, |_| {});
+        assert_same(&game, &serde_json::from_str(&json).unwrap());
+        let json = with_desk(&game, |desk| desk[PILE_START][0] = desk[PILE_START + 1][0]);
+        assert!(serde_json::from_str::<Game>(&json).is_err());
+        let json = with_desk(&game, |desk| {
+            let card = desk[PILE_START].pop().unwrap();
+            desk[CELL_START].push(card);
+        });
+        assert!(serde_json::from_str::<Game>(&json).is_err());
+
+        // Only three cells are open.
+        let mut blocked = game;
+        blocked.move_card(PILE_START, CELL_START + 3);
+        let json = serde_json::to_string(&blocked).unwrap();
+        assert!(serde_json::from_str::<Game>(&json).is_err());
+    }
+
+    #[test]
+    fn binary() {
+        let card = Card::from_u8(51);
+        let bytes = bincode::serialize(&card).unwrap();
+        assert_eq!(vec![51], bytes);
+        assert_eq!(card, bincode::deserialize::<Card>(&bytes).unwrap());
+        assert!(bincode::deserialize::<Card>(&[52]).is_err());
+
+        let mv = Move::at(PILE_START + 4, BASE_START);
+        let bytes = bincode::serialize(&mv).unwrap();
+        assert_eq!(vec![PILE_START as u8 + 4, BASE_START as u8], bytes);
+        assert_eq!(mv, bincode::deserialize::<Move>(&bytes).unwrap());
+
+        let game = game();
+        let bytes = bincode::serialize(&game).unwrap();
+        assert_same(&game, &bincode::deserialize(&bytes).unwrap());
+        assert!(bytes.len() < serde_json::to_string(&game).unwrap().len());
+
+        let key = game.get_invariant();
+        let bytes = bincode::serialize(&key).unwrap();
+        assert_eq!(key, bincode::deserialize(&bytes).unwrap());
+
+        let path: Path = game.path().clone();
+        let bytes = bincode::serialize(&path).unwrap();
+        assert_eq!(path, bincode::deserialize::<Path>(&bytes).unwrap());
+    }
+}
@@ -7,11 +7,24 @@ pub trait Consumer<T> {
     fn accept(&mut self, t: T) -> bool;
 }
 
+/// Closures are consumers too, e.g. `|mv| { moves.push(mv); moves.len() < 3 }`.
+impl<T, F: FnMut(T) -> bool> Consumer<T> for F {
+    fn accept(&mut self, t: T) -> bool {
+        self(t)
+    }
+}
+
 /// Single operation consumer.
 pub struct SingleConsumer<T>(Option<T>);
 /// Consumes all operations into a [`Vec`].
 pub struct TotalConsumer<T>(Vec<T>);
 
+impl<T> Default for SingleConsumer<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<T> SingleConsumer<T> {
     pub fn new() -> Self {
         Self(None)
@@ -57,6 +70,12 @@ impl<T> Consumer<T> for SingleConsumer<T> {
     }
 }
 
+impl<T> Default for TotalConsumer<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<T> TotalConsumer<T> {
     pub fn new() -> Self {
         Self(Vec::new())
@@ -8,6 +8,15 @@ where
     data: BTreeMap<K, Vec<V>>,
 }
 
+impl<K, V> Default for Grader<K, V>
+where
+    K: Ord,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<K, V> Grader<K, V>
 where
     K: Ord,
@@ -26,16 +35,20 @@ where
         self.data.values().map(|row| row.len()).sum()
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
     pub fn grade_num(&self) -> usize {
         self.data.len()
     }
 
     pub fn add(&mut self, grade: K, value: V) {
-        let row = self.data.entry(grade).or_insert_with(Vec::new);
+        let row = self.data.entry(grade).or_default();
         row.push(value);
     }
 
-    pub fn grades(&self) -> Keys<K, Vec<V>> {
+    pub fn grades(&self) -> Keys<'_, K, Vec<V>> {
         self.data.keys()
     }
 
@@ -1,6 +1,8 @@
 //! Contains miscellaneous utility structures and functions.
 mod consumer;
 mod grader;
+mod random;
 
 pub use consumer::*;
 pub use grader::*;
+pub use random::*;
@@ -0,0 +1,96 @@
+//! # WebAssembly bindings
+//! Exposes deal generation, move validation, hints and solving to JavaScript.
+//! Positions are given the same way as in the demo links: a deal number and a hex path.
+//!
+//! Build with `wasm-pack build wasm` and test with `wasm-pack test --node wasm`.
+
+use wasm_bindgen::prelude::*;
+
+use freecell_solver::deck;
+use freecell_solver::freecell::{
+    demo_link, hint_with, path_from_hex, path_to_hex, Game, Limits, Solver,
+};
+
+/// Returns the deal number, or [`None`] unless the JavaScript number is a non-negative integer.
+fn deal_number(seed: f64) -> Option<u64> {
+    (seed >= 0.0 && seed.fract() == 0.0).then_some(seed as u64)
+}
+
+/// Returns the game position reached from the deal by the hex path,
+/// or [`None`] if the path is malformed or contains an illegal move.
+fn position(seed: f64, path: &str) -> Option<Game> {
+    let mut game = Game::new();
+    game.deal(&deck::deal(deal_number(seed)?));
+    for mv in path_from_hex(path)? {
+        if !game.is_valid_move(mv.giver().index(), mv.taker().index()) {
+            return None;
+        }
+        game.apply(&mv);
+    }
+    Some(game)
+}
+
+fn limits(done_max: u32) -> Limits {
+    Limits {
+        done_max: done_max as usize,
+        any: true,
+        ..Limits::default()
+    }
+}
+
+/// Returns the shuffled deck of the deal as a card string, e.g. "K♦3♠4♠J♠...".
+/// Cards are dealt into the piles from left to right, row by row.
+#[wasm_bindgen]
+pub fn deal(seed: f64) -> Option<String> {
+    deal_number(seed).map(|seed| deck::to_string(&deck::deal(seed)))
+}
+
+/// Returns `true` if the hex move (e.g. "7c") is legal after the hex path.
+#[wasm_bindgen(js_name = isValidMove)]
+pub fn is_valid_move(seed: f64, path: &str, mv: &str) -> bool {
+    match (position(seed, path), path_from_hex(mv)) {
+        (Some(game), Some(moves)) => match moves.as_slice() {
+            [mv] => game.is_valid_move(mv.giver().index(), mv.taker().index()),
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+/// Returns the demo link of the hex path, or `undefined` for an invalid deal number.
+#[wasm_bindgen]
+pub fn link(seed: f64, path: &str) -> Option<String> {
+    deal_number(seed).map(|seed| demo_link(seed, path))
+}
+
+/// A suggested move.
+#[wasm_bindgen(js_name = Hint, getter_with_clone)]
+pub struct JsHint {
+    /// The hex move, e.g. "7c".
+    #[wasm_bindgen(js_name = move)]
+    pub mv: String,
+    /// `true` if the move leads to a found solution, `false` if it's the heuristic best one.
+    pub winning: bool,
+}
+
+/// Returns the best next move after the hex path, processing at most `done_max` states.
+#[wasm_bindgen]
+pub fn hint(seed: f64, path: &str, done_max: u32) -> Option<JsHint> {
+    let game = position(seed, path)?;
+    let hint = hint_with(&game, &limits(done_max))?;
+    Some(JsHint {
+        mv: path_to_hex(&vec![hint.mv]),
+        winning: hint.winning,
+    })
+}
+
+/// Solves the game from the position after the hex path, processing at most `done_max` states.
+/// Returns the whole hex path (the given one followed by the solution), as used in the demo links.
+#[wasm_bindgen]
+pub fn solve(seed: f64, path: &str, done_max: u32) -> Option<String> {
+    let game = position(seed, path)?;
+    let mut solver = Solver::new();
+    solver.start_from(&game);
+    let solution = solver.solve(&limits(done_max))?;
+    Some(format!("{}{}", path, path_to_hex(solution)))
+}
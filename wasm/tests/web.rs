@@ -0,0 +1,53 @@
+//! Run with `wasm-pack test --node wasm`, or natively with `cargo test`.
+
+use freecell_solver::deck;
+use freecell_solver_wasm::*;
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen_test::*;
+
+const DEAL: f64 = 173205951.0;
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+fn deals() {
+    let cards = deal(DEAL).expect("Should be dealt!");
+    assert_eq!(deck::to_string(&deck::deal(DEAL as u64)), cards);
+    assert!(deal(-1.0).is_none());
+    assert!(deal(0.5).is_none());
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+fn moves() {
+    assert!(is_valid_move(DEAL, "", "48"));
+    assert!(is_valid_move(DEAL, "48", "7c"));
+    assert!(!is_valid_move(DEAL, "48", "c7"));
+    assert!(!is_valid_move(DEAL, "48", "7c7d"));
+    assert!(!is_valid_move(DEAL, "4", "7c"));
+    assert!(!is_valid_move(DEAL, "c7", "7c"));
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+fn hints() {
+    assert!(hint(DEAL, "zz", 1000).is_none());
+
+    let found = hint(DEAL, "4871317c", 100000).expect("Should be found!");
+    assert!(found.winning);
+    assert!(is_valid_move(DEAL, "4871317c", &found.mv));
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+fn solutions() {
+    let prefix = "4871317c";
+    let path = solve(DEAL, prefix, 1000000).expect("Should be solved!");
+    assert!(path.starts_with(prefix));
+
+    // All the moves are legal and nothing is left to solve at the end.
+    assert_eq!(Some(path.clone()), solve(DEAL, &path, 1));
+
+    let url = link(DEAL, &path).expect("Should be linked!");
+    assert!(url.ends_with(&format!("deal=173205951&path={}", path)));
+    assert!(link(-1.0, &path).is_none());
+}